@@ -1,60 +1,195 @@
-use crate::ai::nlputils::extract_candidate_phrases;
+use crate::ai::math::cosine_similarity;
+use crate::ai::nlputils::extract_candidates;
 use crate::ai::sentence_transformer::generate_embedding;
+use crate::confapi::{get_config, Config};
+use rust_stemmers::{Algorithm, Stemmer};
+use std::collections::HashMap;
 use std::error::Error;
 
-/// Compute cosine similarity between two vectors.
-fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
-    let dot: f32 = a.iter().zip(b.iter()).map(|(x, y)| x * y).sum();
-    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
-    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
-    if norm_a == 0.0 || norm_b == 0.0 {
-        0.0
-    } else {
-        dot / (norm_a * norm_b)
+/// Number of tags returned by [`generate_tags`].
+pub const DEFAULT_TAG_COUNT: usize = 3;
+
+/// Generate tags for an input text note.
+///
+/// Returns the names of the top [`DEFAULT_TAG_COUNT`] candidates from [`generate_tags_scored`].
+pub fn generate_tags(text: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    // Neither scoring strategy produces scores below -1.0, so this keeps every candidate.
+    let scored = generate_tags_scored(text, -1.0)?;
+    let final_tags = scored
+        .into_iter()
+        .take(DEFAULT_TAG_COUNT)
+        .map(|(phrase, _sim)| phrase)
+        .collect();
+
+    Ok(final_tags)
+}
+
+/// Strategy used to score candidate phrases, selected by `ai.autotagging.mode`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaggingMode {
+    /// `"semantic"`: rank candidates by embedding similarity to the note. Requires the model.
+    Semantic,
+    /// `"statistical"`: rank candidates by RAKE keyword scores over the note. Needs no model.
+    Statistical,
+}
+
+impl TaggingMode {
+    /// Determines the tagging mode from the configuration.
+    ///
+    /// Defaults to `Semantic` when the mode is missing or unrecognized.
+    pub fn from_config(config: &Config) -> TaggingMode {
+        let mode = config
+            .ai
+            .as_ref()
+            .and_then(|ai| ai.autotagging.as_ref())
+            .and_then(|autotagging| autotagging.mode.as_deref());
+        match mode {
+            Some(m) if m.eq_ignore_ascii_case("statistical") => TaggingMode::Statistical,
+            _ => TaggingMode::Semantic,
+        }
     }
 }
 
-/// Generate tags for an input text note.
+/// Generate scored tag candidates for an input text note.
+///
+/// The scoring strategy is read from the configuration (see [`TaggingMode`]).
+/// Returns every candidate scoring at least `threshold`, highest score first.
+pub fn generate_tags_scored(
+    text: &str,
+    threshold: f32,
+) -> Result<Vec<(String, f32)>, Box<dyn Error>> {
+    let mode = get_config()
+        .map(|config| TaggingMode::from_config(&config))
+        .unwrap_or(TaggingMode::Semantic);
+    generate_tags_scored_with_mode(text, threshold, mode)
+}
+
+/// Generate scored tag candidates for an input text note using an explicit mode.
 ///
 /// The process is as follows:
-/// 1. Generate an embedding for the overall text.
-/// 2. Extract candidate phrases (unigrams and bigrams) using the nlputils module.
-/// 3. For each candidate phrase, generate its embedding.
-/// 4. Compute cosine similarity between the overall embedding and each candidate embedding.
-/// 5. Return the top 3 candidate phrases with the highest similarity as tags.
-pub fn generate_tags(text: &str) -> Result<Vec<String>, Box<dyn Error>> {
-    // 1. Generate the overall embedding for the entire text.
+/// 1. Extract candidate phrases using the nlputils module, with the extractor selected by
+///    `ai.autotagging.extractor` and phrases of up to `ai.autotagging.max_ngram` words
+///    (unigrams and bigrams by default).
+/// 2. Score each candidate with the selected strategy:
+///    - `Semantic`: cosine similarity between the note's embedding and the candidate's embedding.
+///    - `Statistical`: normalized RAKE score of the candidate in the note (see [`score_candidates_statistical`]).
+/// 3. Return every candidate scoring at least `threshold`, highest score first.
+pub fn generate_tags_scored_with_mode(
+    text: &str,
+    threshold: f32,
+    mode: TaggingMode,
+) -> Result<Vec<(String, f32)>, Box<dyn Error>> {
+    // 1. Extract candidate phrases from the text.
+    let candidate_phrases = extract_candidates(text)?;
+
+    // 2. Score candidates with the selected strategy.
+    let mut candidate_scores = match mode {
+        TaggingMode::Semantic => score_candidates_semantic(text, candidate_phrases)?,
+        TaggingMode::Statistical => score_candidates_statistical(text, candidate_phrases),
+    };
+
+    // 3. Filter by threshold and sort by score (highest first).
+    candidate_scores.retain(|(_, score)| *score >= threshold);
+    candidate_scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    Ok(candidate_scores)
+}
+
+/// Scores candidates by cosine similarity between their embedding and the note's embedding.
+fn score_candidates_semantic(
+    text: &str,
+    candidates: Vec<String>,
+) -> Result<Vec<(String, f32)>, Box<dyn Error>> {
+    // Generate the overall embedding for the entire text.
     let overall_embeddings = generate_embedding(text)?;
     // Assume the first (or only) embedding represents the note.
     let overall_embedding = overall_embeddings
-        .get(0)
+        .first()
         .ok_or("Failed to generate overall embedding")?;
 
-    // 2. Extract candidate phrases from the text.
-    let candidate_phrases = extract_candidate_phrases(text)?;
-
-    // 3. For each candidate, generate its embedding and compute similarity.
+    // For each candidate, generate its embedding and compute similarity.
     let mut candidate_scores = Vec::new();
-    for candidate in candidate_phrases {
+    for candidate in candidates {
         let candidate_embeddings = generate_embedding(&candidate)?;
         let candidate_embedding = candidate_embeddings
-            .get(0)
+            .first()
             .ok_or("Failed to generate candidate embedding")?;
         let similarity = cosine_similarity(overall_embedding, candidate_embedding);
         candidate_scores.push((candidate, similarity));
     }
+    Ok(candidate_scores)
+}
 
-    // 4. Sort candidates by similarity (highest first).
-    candidate_scores.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+/// Scores candidates with RAKE (Rapid Automatic Keyword Extraction), without using embeddings.
+///
+/// Each candidate's occurrences in the text are counted, comparing words by stem so
+/// "certificate" also matches "certificates". Every word then gets the RAKE score
+/// `degree / frequency`: its frequency is the number of candidate occurrences containing
+/// it, and its degree adds the length of each of those occurrences. A candidate scores the
+/// sum of its word scores, so words that mostly appear inside longer phrases outrank filler
+/// words that appear often on their own. Candidates that don't occur score 0, and scores
+/// are normalized to the range [0, 1].
+pub fn score_candidates_statistical(text: &str, candidates: Vec<String>) -> Vec<(String, f32)> {
+    let stemmer = Stemmer::create(Algorithm::English);
+    let stem_words = |s: &str| -> Vec<String> {
+        s.split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+            .map(|w| stemmer.stem(&w.to_lowercase()).into_owned())
+            .collect()
+    };
 
-    // 5. Select the top 3 candidate phrases as final tags.
-    let final_tags = candidate_scores
+    let words = stem_words(text);
+    let phrases: Vec<(String, Vec<String>, usize)> = candidates
         .into_iter()
-        .take(3)
-        .map(|(phrase, _sim)| phrase)
+        .map(|candidate| {
+            let phrase = stem_words(&candidate);
+            let occurrences = if phrase.is_empty() {
+                0
+            } else {
+                words
+                    .windows(phrase.len())
+                    .filter(|w| *w == phrase.as_slice())
+                    .count()
+            };
+            (candidate, phrase, occurrences)
+        })
         .collect();
 
-    Ok(final_tags)
+    // Word frequency and degree over all candidate occurrences.
+    let mut frequency: HashMap<&str, usize> = HashMap::new();
+    let mut degree: HashMap<&str, usize> = HashMap::new();
+    for (_, phrase, occurrences) in &phrases {
+        for word in phrase {
+            *frequency.entry(word.as_str()).or_insert(0) += occurrences;
+            *degree.entry(word.as_str()).or_insert(0) += occurrences * phrase.len();
+        }
+    }
+
+    let mut candidate_scores: Vec<(String, f32)> = phrases
+        .iter()
+        .map(|(candidate, phrase, occurrences)| {
+            let score = if *occurrences == 0 {
+                0.0
+            } else {
+                phrase
+                    .iter()
+                    .map(|word| degree[word.as_str()] as f32 / frequency[word.as_str()] as f32)
+                    .sum()
+            };
+            (candidate.clone(), score)
+        })
+        .collect();
+
+    let max_score = candidate_scores
+        .iter()
+        .map(|(_, score)| *score)
+        .fold(0.0, f32::max);
+    if max_score > 0.0 {
+        for (_, score) in candidate_scores.iter_mut() {
+            *score /= max_score;
+        }
+    }
+    candidate_scores
 }
 
 #[cfg(test)]
@@ -112,4 +247,35 @@ ai:
 
         println!("Generated tags: {:?}", tags);
     }
+
+    #[test]
+    fn test_score_candidates_statistical() {
+        let text = "The async runtime schedules tasks. Rust ships no async runtime, so Rust users pick one.";
+        let candidates = vec![
+            "rust".to_string(),
+            "task".to_string(),
+            "async runtime".to_string(),
+            "garbage".to_string(),
+        ];
+        let scores = score_candidates_statistical(text, candidates);
+        let score_of = |name: &str| scores.iter().find(|(c, _)| c == name).unwrap().1;
+
+        // Each word of "async runtime" has degree 4 and frequency 2, so the phrase scores 4.
+        assert_eq!(score_of("async runtime"), 1.0);
+        // Standalone words score 1 however often they occur, which keeps filler words low.
+        assert_eq!(score_of("rust"), 0.25);
+        // "task" matches "tasks" by stem.
+        assert_eq!(score_of("task"), 0.25);
+        assert_eq!(score_of("garbage"), 0.0);
+    }
+
+    #[test]
+    fn test_tagging_mode_from_config() {
+        let config: Config =
+            serde_yaml::from_str("ai:\n  autotagging:\n    mode: statistical\n").unwrap();
+        assert_eq!(TaggingMode::from_config(&config), TaggingMode::Statistical);
+
+        let config: Config = serde_yaml::from_str("vault_dir: /tmp\n").unwrap();
+        assert_eq!(TaggingMode::from_config(&config), TaggingMode::Semantic);
+    }
 }
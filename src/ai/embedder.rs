@@ -0,0 +1,314 @@
+use crate::ai::sentence_transformer::{encode, get_model_dir, load_model, AiError};
+use crate::confapi::{Config, ConfigError};
+use async_trait::async_trait;
+use once_cell::sync::OnceCell;
+use rust_bert::pipelines::sentence_embeddings::SentenceEmbeddingsModel;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+/// A provider that turns texts into embedding vectors.
+///
+/// Code that takes `&dyn Embedder` works with the local sentence-transformer model
+/// ([`LocalEmbedder`]) as well as a remote service ([`HttpEmbedder`]). Embedders are
+/// `Send + Sync` and so are their errors, so async code holding one can be spawned.
+/// Implementations must not block the async runtime.
+#[async_trait]
+pub trait Embedder: Send + Sync {
+    /// Returns one embedding per input text, in input order.
+    async fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>, Box<dyn Error + Send + Sync>>;
+
+    /// Returns the dimension of the embeddings this provider produces.
+    async fn dimension(&self) -> Result<usize, Box<dyn Error + Send + Sync>>;
+}
+
+/// Embeds texts with a local sentence-transformer model (rust-bert).
+///
+/// The model is loaded on first use and kept for the embedder's lifetime (clones share
+/// it). Loading and encoding run on tokio's blocking thread pool.
+#[derive(Clone)]
+pub struct LocalEmbedder {
+    model_dir: PathBuf,
+    model: Arc<OnceCell<Mutex<SentenceEmbeddingsModel>>>,
+}
+
+impl std::fmt::Debug for LocalEmbedder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LocalEmbedder")
+            .field("model_dir", &self.model_dir)
+            .field("loaded", &self.model.get().is_some())
+            .finish()
+    }
+}
+
+impl LocalEmbedder {
+    /// Uses the model in the config directory (see `sentence_transformer::get_model_dir`).
+    pub fn new() -> Self {
+        Self::with_model_dir(get_model_dir())
+    }
+
+    /// Uses the model in `model_dir`.
+    pub fn with_model_dir(model_dir: PathBuf) -> Self {
+        Self {
+            model_dir,
+            model: Arc::new(OnceCell::new()),
+        }
+    }
+
+    /// Encodes `texts` with the model, loading it first if needed. This blocks, so call it
+    /// from a blocking thread.
+    fn encode_blocking(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>, AiError> {
+        let model = self
+            .model
+            .get_or_try_init(|| load_model(&self.model_dir).map(Mutex::new))?;
+        // A panic while encoding doesn't leave the model in a bad state, so ignore poisoning.
+        let model = model.lock().unwrap_or_else(|e| e.into_inner());
+        encode(&model, texts)
+    }
+
+    /// Runs [`encode_blocking`](Self::encode_blocking) on tokio's blocking thread pool.
+    async fn encode_owned(
+        &self,
+        texts: Vec<String>,
+    ) -> Result<Vec<Vec<f32>>, Box<dyn Error + Send + Sync>> {
+        let embedder = self.clone();
+        let embeddings = tokio::task::spawn_blocking(move || {
+            let texts: Vec<&str> = texts.iter().map(String::as_str).collect();
+            embedder.encode_blocking(&texts)
+        })
+        .await??;
+        Ok(embeddings)
+    }
+}
+
+impl Default for LocalEmbedder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Embedder for LocalEmbedder {
+    async fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>, Box<dyn Error + Send + Sync>> {
+        if texts.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.encode_owned(texts.iter().map(|text| text.to_string()).collect())
+            .await
+    }
+
+    async fn dimension(&self) -> Result<usize, Box<dyn Error + Send + Sync>> {
+        let embeddings = self
+            .encode_owned(vec!["dimension probe".to_string()])
+            .await?;
+        let first = embeddings.first().ok_or(AiError::NoOutput)?;
+        Ok(first.len())
+    }
+}
+
+/// Embeds texts by POSTing them to an OpenAI `/embeddings`-compatible HTTP endpoint.
+///
+/// The request body is `{"model": ..., "input": [...]}` and the response is expected to
+/// contain `data[].embedding`, with an optional `data[].index` giving each input's position.
+#[derive(Debug, Clone)]
+pub struct HttpEmbedder {
+    endpoint: String,
+    model: String,
+    api_key: Option<String>,
+    dimension: usize,
+    client: reqwest::Client,
+}
+
+impl HttpEmbedder {
+    /// Creates an embedder for `endpoint` (the full URL, e.g. `https://api.openai.com/v1/embeddings`).
+    ///
+    /// `dimension` is the size of the vectors `model` returns; the service is not asked, but
+    /// responses with vectors of another size are rejected.
+    pub fn new(endpoint: &str, model: &str, dimension: usize) -> Self {
+        Self {
+            endpoint: endpoint.to_string(),
+            model: model.to_string(),
+            api_key: None,
+            dimension,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Sends `api_key` as a bearer token with every request.
+    pub fn with_api_key(mut self, api_key: &str) -> Self {
+        self.api_key = Some(api_key.to_string());
+        self
+    }
+}
+
+#[derive(Serialize)]
+struct EmbeddingRequest<'a> {
+    model: &'a str,
+    input: &'a [&'a str],
+}
+
+#[derive(Deserialize)]
+struct EmbeddingResponse {
+    data: Vec<EmbeddingData>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingData {
+    embedding: Vec<f32>,
+    index: Option<usize>,
+}
+
+#[async_trait]
+impl Embedder for HttpEmbedder {
+    async fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>, Box<dyn Error + Send + Sync>> {
+        if texts.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut request = self.client.post(&self.endpoint).json(&EmbeddingRequest {
+            model: &self.model,
+            input: texts,
+        });
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+        let response: EmbeddingResponse = request.send().await?.error_for_status()?.json().await?;
+        embeddings_in_input_order(response, texts.len(), self.dimension)
+    }
+
+    async fn dimension(&self) -> Result<usize, Box<dyn Error + Send + Sync>> {
+        Ok(self.dimension)
+    }
+}
+
+/// Orders the response's embeddings by their `index`, checking one came back per input and
+/// that each has `dimension` values.
+fn embeddings_in_input_order(
+    mut response: EmbeddingResponse,
+    expected: usize,
+    dimension: usize,
+) -> Result<Vec<Vec<f32>>, Box<dyn Error + Send + Sync>> {
+    if response.data.len() != expected {
+        return Err(format!(
+            "embedding service returned {} embeddings for {} inputs",
+            response.data.len(),
+            expected
+        )
+        .into());
+    }
+    if let Some(data) = response
+        .data
+        .iter()
+        .find(|data| data.embedding.len() != dimension)
+    {
+        return Err(format!(
+            "embedding service returned a vector of dimension {}, expected {}",
+            data.embedding.len(),
+            dimension
+        )
+        .into());
+    }
+    response.data.sort_by_key(|data| data.index);
+    Ok(response
+        .data
+        .into_iter()
+        .map(|data| data.embedding)
+        .collect())
+}
+
+/// Builds the embedder selected by `ai.embedder` in the configuration.
+///
+/// `provider: http` requires `endpoint`, `model` and `dimension`; the API key is read
+/// from the environment variable named by `api_key_env`, so it never lives in the config
+/// file. Anything else (including no `ai.embedder` section) gives a [`LocalEmbedder`].
+pub fn embedder_from_config(config: &Config) -> Result<Box<dyn Embedder>, ConfigError> {
+    let Some(settings) = config.ai.as_ref().and_then(|ai| ai.embedder.as_ref()) else {
+        return Ok(Box::new(LocalEmbedder::new()));
+    };
+    if !settings
+        .provider
+        .as_deref()
+        .is_some_and(|provider| provider.eq_ignore_ascii_case("http"))
+    {
+        return Ok(Box::new(LocalEmbedder::new()));
+    }
+
+    let missing =
+        |field: &str| ConfigError::InvalidConfig(format!("Missing 'ai.embedder.{}' field", field));
+    let endpoint = settings
+        .endpoint
+        .as_deref()
+        .ok_or_else(|| missing("endpoint"))?;
+    let model = settings.model.as_deref().ok_or_else(|| missing("model"))?;
+    let dimension = settings.dimension.ok_or_else(|| missing("dimension"))?;
+
+    let mut embedder = HttpEmbedder::new(endpoint, model, dimension);
+    if let Some(var) = &settings.api_key_env {
+        let api_key = std::env::var(var).map_err(|_| {
+            ConfigError::InvalidConfig(format!("Environment variable '{}' is not set", var))
+        })?;
+        embedder = embedder.with_api_key(&api_key);
+    }
+    Ok(Box::new(embedder))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_embeddings_in_input_order() {
+        let response: EmbeddingResponse = serde_json::from_str(
+            r#"{"data": [{"embedding": [2.0], "index": 1}, {"embedding": [1.0], "index": 0}]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            embeddings_in_input_order(response, 2, 1).unwrap(),
+            vec![vec![1.0], vec![2.0]]
+        );
+
+        let response: EmbeddingResponse =
+            serde_json::from_str(r#"{"data": [{"embedding": [1.0]}]}"#).unwrap();
+        assert!(embeddings_in_input_order(response, 2, 1).is_err());
+    }
+
+    #[test]
+    fn test_embeddings_with_wrong_dimension_are_rejected() {
+        let response: EmbeddingResponse = serde_json::from_str(
+            r#"{"data": [{"embedding": [1.0, 2.0], "index": 0}, {"embedding": [3.0], "index": 1}]}"#,
+        )
+        .unwrap();
+        let err = embeddings_in_input_order(response, 2, 2).unwrap_err();
+        assert!(err.to_string().contains("dimension 1, expected 2"));
+    }
+
+    #[tokio::test]
+    async fn test_local_embedder_without_model() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let embedder = LocalEmbedder::with_model_dir(dir.path().to_path_buf());
+        let err = embedder.embed(&["text"]).await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<AiError>(),
+            Some(AiError::ModelNotFound(_))
+        ));
+        // A failed load isn't cached, so installing the model later works.
+        assert!(embedder.model.get().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_embedder_from_config() {
+        let config: Config = serde_yaml::from_str(
+            "ai:\n  embedder:\n    provider: http\n    endpoint: http://localhost:8080/v1/embeddings\n    model: text-embedding-3-small\n    dimension: 1536\n",
+        )
+        .unwrap();
+        let embedder = embedder_from_config(&config).unwrap();
+        assert_eq!(embedder.dimension().await.unwrap(), 1536);
+
+        let config: Config =
+            serde_yaml::from_str("ai:\n  embedder:\n    provider: http\n").unwrap();
+        assert!(matches!(
+            embedder_from_config(&config),
+            Err(ConfigError::InvalidConfig(_))
+        ));
+    }
+}
@@ -0,0 +1,71 @@
+/// Panics with a descriptive message if the two vectors differ in length.
+/// A mismatch almost always means embeddings from two different models are being mixed.
+fn check_lengths(a: &[f32], b: &[f32]) {
+    assert_eq!(
+        a.len(),
+        b.len(),
+        "vector length mismatch: {} vs {}",
+        a.len(),
+        b.len()
+    );
+}
+
+/// Compute the dot product of two vectors.
+///
+/// Panics if the vectors differ in length.
+pub fn dot(a: &[f32], b: &[f32]) -> f32 {
+    check_lengths(a, b);
+    a.iter().zip(b.iter()).map(|(x, y)| x * y).sum()
+}
+
+/// Compute the Euclidean (L2) distance between two vectors.
+///
+/// Panics if the vectors differ in length.
+pub fn l2_distance(a: &[f32], b: &[f32]) -> f32 {
+    check_lengths(a, b);
+    a.iter()
+        .zip(b.iter())
+        .map(|(x, y)| (x - y) * (x - y))
+        .sum::<f32>()
+        .sqrt()
+}
+
+/// Compute cosine similarity between two vectors.
+///
+/// Returns `0.0` if either vector has zero magnitude. Panics if the vectors differ in length.
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let dot = dot(a, b);
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a * norm_b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dot_and_l2_distance() {
+        let a = [1.0, 2.0, 3.0];
+        let b = [4.0, 6.0, 3.0];
+        assert_eq!(dot(&a, &b), 25.0);
+        assert_eq!(l2_distance(&a, &b), 5.0);
+    }
+
+    #[test]
+    fn test_cosine_similarity() {
+        assert!((cosine_similarity(&[1.0, 0.0], &[2.0, 0.0]) - 1.0).abs() < 1e-6);
+        assert!(cosine_similarity(&[1.0, 0.0], &[0.0, 1.0]).abs() < 1e-6);
+        assert_eq!(cosine_similarity(&[0.0, 0.0], &[1.0, 1.0]), 0.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "vector length mismatch")]
+    fn test_mismatched_lengths_panic() {
+        cosine_similarity(&[1.0, 2.0], &[1.0, 2.0, 3.0]);
+    }
+}
@@ -1,3 +1,5 @@
 pub mod autotag;
+pub mod embedder;
+pub mod math;
 pub mod nlputils;
 pub mod sentence_transformer;
@@ -1,12 +1,122 @@
-use crate::confapi::get_config_dir;
+use crate::confapi::{get_config, get_config_dir, Config, ConfigError};
+use flate2::read::GzDecoder;
 use nlprule::Tokenizer;
 use std::collections::{HashMap, HashSet};
 use std::error::Error;
+use std::fs;
+use std::io;
 use std::path::PathBuf;
 
 // Add the stemmer crate.
 use rust_stemmers::{Algorithm, Stemmer};
 
+/// File name of the nlprule tokenizer binary in the config directory.
+pub const TOKENIZER_FILE_NAME: &str = "en_tokenizer.bin";
+
+/// Release URL of the gzipped English tokenizer matching the nlprule version in use.
+pub const TOKENIZER_DOWNLOAD_URL: &str =
+    "https://github.com/bminixhofer/nlprule/releases/download/0.6.4/en_tokenizer.bin.gz";
+
+/// Returns the expected path of the tokenizer binary in the config directory.
+pub fn get_tokenizer_path() -> PathBuf {
+    let mut path = get_config_dir();
+    path.push(TOKENIZER_FILE_NAME);
+    path
+}
+
+/// Downloads the tokenizer binary into the config directory and returns its path.
+///
+/// The release asset is gzipped, so it is decompressed while writing.
+pub fn download_tokenizer() -> Result<PathBuf, Box<dyn Error>> {
+    let tokenizer_path = get_tokenizer_path();
+    if let Some(parent) = tokenizer_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let response = reqwest::blocking::get(TOKENIZER_DOWNLOAD_URL)?.error_for_status()?;
+    let mut decoder = GzDecoder::new(response);
+
+    // Write to a temporary file first so a failed download doesn't leave a truncated binary behind.
+    let partial_path = tokenizer_path.with_extension("bin.part");
+    let mut file = fs::File::create(&partial_path)?;
+    io::copy(&mut decoder, &mut file)?;
+    fs::rename(&partial_path, &tokenizer_path)?;
+
+    Ok(tokenizer_path)
+}
+
+/// Candidate phrase extractor, selected by `ai.autotagging.extractor`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CandidateExtractor {
+    /// `"nlprule"`: POS-tag based extraction (see [`extract_candidate_phrases`]).
+    /// Requires the tokenizer binary.
+    Nlprule,
+    /// `"simple"`: word-boundary based extraction (see [`extract_candidate_phrases_simple`]).
+    /// Less accurate, but needs no tokenizer file.
+    Simple,
+}
+
+impl CandidateExtractor {
+    /// Determines the extractor from the configuration.
+    ///
+    /// Defaults to `Nlprule` when the extractor is missing or unrecognized.
+    pub fn from_config(config: &Config) -> CandidateExtractor {
+        let extractor = config
+            .ai
+            .as_ref()
+            .and_then(|ai| ai.autotagging.as_ref())
+            .and_then(|autotagging| autotagging.extractor.as_deref());
+        match extractor {
+            Some(e) if e.eq_ignore_ascii_case("simple") => CandidateExtractor::Simple,
+            _ => CandidateExtractor::Nlprule,
+        }
+    }
+}
+
+/// Returns `ai.autotagging.max_ngram`, or [`DEFAULT_MAX_NGRAM`] when it is not set.
+pub fn max_ngram_from_config(config: &Config) -> usize {
+    config
+        .ai
+        .as_ref()
+        .and_then(|ai| ai.autotagging.as_ref())
+        .and_then(|autotagging| autotagging.max_ngram)
+        .unwrap_or(DEFAULT_MAX_NGRAM)
+}
+
+/// Extract candidate phrases with the extractor and maximum n-gram size selected in the
+/// configuration.
+///
+/// Falls back to `Nlprule` and [`DEFAULT_MAX_NGRAM`] if the configuration can't be read.
+pub fn extract_candidates(text: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    let (extractor, max_ngram) = get_config()
+        .map(|config| {
+            (
+                CandidateExtractor::from_config(&config),
+                max_ngram_from_config(&config),
+            )
+        })
+        .unwrap_or((CandidateExtractor::Nlprule, DEFAULT_MAX_NGRAM));
+    extract_candidates_with(text, extractor, max_ngram)
+}
+
+/// Extract candidate phrases of up to `max_ngram` words with `extractor`.
+pub fn extract_candidates_with(
+    text: &str,
+    extractor: CandidateExtractor,
+    max_ngram: usize,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    match extractor {
+        CandidateExtractor::Nlprule => extract_candidate_phrases_with_ngrams(text, max_ngram),
+        CandidateExtractor::Simple => Ok(extract_candidate_phrases_simple_with_ngrams(
+            text, max_ngram,
+        )),
+    }
+}
+
+/// Default maximum n-gram size used by [`extract_candidate_phrases`] and
+/// [`extract_candidate_phrases_simple`].
+pub const DEFAULT_MAX_NGRAM: usize = 2;
+
 /// Extract candidate phrases from the text.
 ///
 /// Unigrams are added if their POS tag is "JJ" (adjective) or starts with "NN" (noun).
@@ -14,9 +124,26 @@ use rust_stemmers::{Algorithm, Stemmer};
 /// The candidates are normalized (trimmed and lowercased) and deduplicated. Additionally,
 /// for single-word candidates we apply stemming to remove variations (e.g. "certificates" and "certificate").
 pub fn extract_candidate_phrases(text: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    extract_candidate_phrases_with_ngrams(text, DEFAULT_MAX_NGRAM)
+}
+
+/// Extract candidate phrases of up to `max_ngram` tokens from the text.
+///
+/// Follows the same rules as [`extract_candidate_phrases`] for unigrams and bigrams.
+/// N-grams of three or more tokens are added when every token is a candidate token.
+/// A `max_ngram` of 0 is treated as 1.
+///
+/// Note that larger n-grams produce many more candidates, and autotagging generates
+/// one embedding per candidate, so raising `max_ngram` increases tagging time.
+pub fn extract_candidate_phrases_with_ngrams(
+    text: &str,
+    max_ngram: usize,
+) -> Result<Vec<String>, Box<dyn Error>> {
     // Build the path to "en_tokenizer.bin" in the config directory.
-    let mut tokenizer_path: PathBuf = get_config_dir();
-    tokenizer_path.push("en_tokenizer.bin");
+    let tokenizer_path = get_tokenizer_path();
+    if !tokenizer_path.exists() {
+        return Err(Box::new(ConfigError::MissingTokenizer(tokenizer_path)));
+    }
     let tokenizer_path_str = tokenizer_path.to_str().ok_or("Invalid tokenizer path")?;
 
     // Initialize the tokenizer from the binary file.
@@ -34,6 +161,9 @@ pub fn extract_candidate_phrases(text: &str) -> Result<Vec<String>, Box<dyn Erro
             let pos: &str = token.word().tags()[0].pos().as_str();
             pos == "JJ" || pos.starts_with("NN")
         };
+        let is_noun = |token: &nlprule::types::Token| -> bool {
+            token.word().tags()[0].pos().as_str().starts_with("NN")
+        };
 
         // Extract unigrams.
         for token in tokens.iter() {
@@ -45,27 +175,107 @@ pub fn extract_candidate_phrases(text: &str) -> Result<Vec<String>, Box<dyn Erro
             }
         }
 
-        // Extract bigrams.
-        // Only include bigrams if both tokens are candidates and
-        // if they are not both nouns.
-        for window in tokens.windows(2) {
-            if is_candidate(&window[0]) && is_candidate(&window[1]) {
-                let pos1 = window[0].word().tags()[0].pos().as_str();
-                let pos2 = window[1].word().tags()[0].pos().as_str();
-                // If both tokens are nouns, skip the bigram.
-                if pos1.starts_with("NN") && pos2.starts_with("NN") {
+        // Extract n-grams from bigrams up to `max_ngram`.
+        // Only include n-grams if all tokens are candidates.
+        // Bigrams are additionally skipped if both tokens are nouns.
+        for n in 2..=max_ngram {
+            for window in tokens.windows(n) {
+                if !window.iter().all(is_candidate) {
+                    continue;
+                }
+                // If both tokens of a bigram are nouns, skip it.
+                if n == 2 && is_noun(&window[0]) && is_noun(&window[1]) {
                     continue;
                 }
-                let word1 = window[0].word().text().as_str().trim().to_lowercase();
-                let word2 = window[1].word().text().as_str().trim().to_lowercase();
-                if !word1.is_empty() && !word2.is_empty() {
-                    let phrase = format!("{} {}", word1, word2);
-                    candidates_set.insert(phrase);
+                let words: Vec<String> = window
+                    .iter()
+                    .map(|token| token.word().text().as_str().trim().to_lowercase())
+                    .collect();
+                if words.iter().all(|word| !word.is_empty()) {
+                    candidates_set.insert(words.join(" "));
                 }
             }
         }
     }
 
+    Ok(dedup_candidates(candidates_set))
+}
+
+/// Whitespace-separated common English words that are never candidates for
+/// [`extract_candidate_phrases_simple`].
+const STOP_WORDS: &str = "\
+    a about above after again against all also am an and any are as at be because been \
+    before being below between both but by can could did do does doing down during each few \
+    for from further had has have having he her here hers herself him himself his how i if \
+    in into is it its itself just me more most my myself no nor not now of off on once only \
+    or other our ours ourselves out over own same she should so some such than that the \
+    their theirs them themselves then there these they this those through to too under until \
+    up very was we were what when where which while who whom why will with would you your \
+    yours yourself yourselves";
+
+/// Extract candidate phrases from the text without the nlprule tokenizer.
+///
+/// The text is split into segments at punctuation and into words at every other
+/// non-alphanumeric character. Unigrams are words of at least three characters that are
+/// not stop words or numbers. Bigrams are two adjacent unigram candidates in the same
+/// segment. Runs of two or more capitalized words (e.g. "New York City") are kept as
+/// likely proper noun phrases. Candidates are lowercased and deduplicated the same way
+/// as [`extract_candidate_phrases`].
+pub fn extract_candidate_phrases_simple(text: &str) -> Vec<String> {
+    extract_candidate_phrases_simple_with_ngrams(text, DEFAULT_MAX_NGRAM)
+}
+
+/// Like [`extract_candidate_phrases_simple`], but with n-grams of up to `max_ngram`
+/// adjacent unigram candidates instead of bigrams. A `max_ngram` of 0 is treated as 1.
+/// Capitalized runs are kept whatever their length.
+pub fn extract_candidate_phrases_simple_with_ngrams(text: &str, max_ngram: usize) -> Vec<String> {
+    let is_candidate = |word: &str| {
+        word.chars().count() >= 3
+            && !word.chars().all(|c| c.is_numeric())
+            && !STOP_WORDS
+                .split_whitespace()
+                .any(|stop| stop.eq_ignore_ascii_case(word))
+    };
+    let is_capitalized = |word: &str| word.chars().next().is_some_and(char::is_uppercase);
+
+    let mut candidates_set: HashSet<String> = HashSet::new();
+    for segment in text.split(|c: char| c.is_ascii_punctuation() && c != '\'' && c != '-') {
+        let words: Vec<&str> = segment
+            .split(|c: char| !c.is_alphanumeric() && c != '\'' && c != '-')
+            .map(|word| word.trim_matches(|c| c == '\'' || c == '-'))
+            .filter(|word| !word.is_empty())
+            .collect();
+
+        for word in words.iter().filter(|word| is_candidate(word)) {
+            candidates_set.insert(word.to_lowercase());
+        }
+
+        for n in 2..=max_ngram {
+            for window in words.windows(n) {
+                if window.iter().all(|word| is_candidate(word)) {
+                    candidates_set.insert(window.join(" ").to_lowercase());
+                }
+            }
+        }
+
+        let mut run: Vec<&str> = Vec::new();
+        for word in words.iter().copied().chain(std::iter::once("")) {
+            if is_capitalized(word) && is_candidate(word) {
+                run.push(word);
+                continue;
+            }
+            if run.len() >= 2 {
+                candidates_set.insert(run.join(" ").to_lowercase());
+            }
+            run.clear();
+        }
+    }
+
+    dedup_candidates(candidates_set)
+}
+
+/// Deduplicates single-word candidates by stem and returns all candidates sorted.
+fn dedup_candidates(candidates_set: HashSet<String>) -> Vec<String> {
     // Use the rust_stemmers crate to create an English stemmer.
     let stemmer = Stemmer::create(Algorithm::English);
 
@@ -97,5 +307,57 @@ pub fn extract_candidate_phrases(text: &str) -> Result<Vec<String>, Box<dyn Erro
     final_candidates.extend(multi_word_candidates);
     final_candidates.sort();
 
-    Ok(final_candidates)
+    final_candidates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_candidate_phrases_simple() {
+        let candidates = extract_candidate_phrases_simple(
+            "We visited New York City. The certificates expire soon!",
+        );
+        assert!(candidates.contains(&"new york city".to_string()));
+        assert!(candidates.contains(&"certificates expire".to_string()));
+        // Stop words are never candidates, and bigrams don't cross punctuation.
+        assert!(!candidates.contains(&"the".to_string()));
+        assert!(!candidates.contains(&"city certificates".to_string()));
+    }
+
+    #[test]
+    fn test_max_ngram() {
+        let config: Config =
+            serde_yaml::from_str("ai:\n  autotagging:\n    max_ngram: 3\n").unwrap();
+        let max_ngram = max_ngram_from_config(&config);
+        assert_eq!(max_ngram, 3);
+        let config: Config = serde_yaml::from_str("vault_dir: /tmp\n").unwrap();
+        assert_eq!(max_ngram_from_config(&config), DEFAULT_MAX_NGRAM);
+
+        let text = "rusty metal gears turned slowly";
+        let trigram = "rusty metal gears".to_string();
+        let candidates =
+            extract_candidates_with(text, CandidateExtractor::Simple, max_ngram).unwrap();
+        assert!(candidates.contains(&trigram));
+        let candidates = extract_candidates_with(text, CandidateExtractor::Simple, 2).unwrap();
+        assert!(!candidates.contains(&trigram));
+        assert!(candidates.contains(&"rusty metal".to_string()));
+    }
+
+    #[test]
+    fn test_candidate_extractor_from_config() {
+        let config: Config =
+            serde_yaml::from_str("ai:\n  autotagging:\n    extractor: simple\n").unwrap();
+        assert_eq!(
+            CandidateExtractor::from_config(&config),
+            CandidateExtractor::Simple
+        );
+
+        let config: Config = serde_yaml::from_str("vault_dir: /tmp\n").unwrap();
+        assert_eq!(
+            CandidateExtractor::from_config(&config),
+            CandidateExtractor::Nlprule
+        );
+    }
 }
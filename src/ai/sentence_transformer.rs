@@ -1,28 +1,205 @@
-use crate::confapi::{get_config, get_config_dir};
-use rust_bert::pipelines::sentence_embeddings::SentenceEmbeddingsBuilder;
+use crate::confapi::{get_config, get_config_dir, ConfigError};
+use rust_bert::pipelines::sentence_embeddings::{
+    SentenceEmbeddingsBuilder, SentenceEmbeddingsModel,
+};
+use rust_bert::RustBertError;
 use std::error::Error;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tch;
 
-pub fn generate_embedding(input_text: &str) -> Result<Vec<Vec<f32>>, Box<dyn Error>> {
+/// Error type for embedding generation.
+///
+/// The variants separate problems a user must fix (`ModelNotFound`, `Config`) from
+/// failures that may be transient and worth retrying (`Encode`, `Device`).
+#[derive(Debug)]
+pub enum AiError {
+    /// No complete model exists at the given directory (see [`is_model_installed`]), or it
+    /// is not a valid UTF-8 path.
+    ModelNotFound(PathBuf),
+    /// The model files exist but could not be loaded.
+    ModelLoad(RustBertError),
+    /// Tokenizing or running the model on the input failed.
+    Encode(RustBertError),
+    /// The compute device failed, e.g. CUDA ran out of memory. Detection is best-effort
+    /// (see [`encode`]); unrecognized device failures are reported as `Encode`.
+    Device(RustBertError),
+    /// The model ran but returned no embedding.
+    NoOutput,
+    /// The configuration could not be read.
+    Config(ConfigError),
+}
+
+impl std::fmt::Display for AiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AiError::ModelNotFound(path) => {
+                write!(
+                    f,
+                    "No sentence-transformer model found at {}",
+                    path.display()
+                )
+            }
+            AiError::ModelLoad(e) => write!(f, "Failed to load model: {}", e),
+            AiError::Encode(e) => write!(f, "Failed to generate embedding: {}", e),
+            AiError::Device(e) => write!(f, "Device error: {}", e),
+            AiError::NoOutput => write!(f, "Model returned no embedding"),
+            AiError::Config(e) => write!(f, "Configuration error: {}", e),
+        }
+    }
+}
+
+impl Error for AiError {}
+
+impl From<ConfigError> for AiError {
+    fn from(err: ConfigError) -> Self {
+        AiError::Config(err)
+    }
+}
+
+/// Name of the sentence-transformer model, which is also its directory name in the config directory.
+/// Since the new AIConfig does not include a model name, we default to "all-MiniLM-L6-v2"
+/// (previously "paraphrase-albert-small-v2").
+pub const MODEL_NAME: &str = "all-MiniLM-L6-v2";
+
+/// Returns the directory the sentence-transformer model is loaded from.
+pub fn get_model_dir() -> PathBuf {
+    get_config_dir().join(MODEL_NAME)
+}
+
+/// Files a model directory must contain for the sentence-embeddings pipeline to load it.
+pub const MODEL_FILES: [&str; 3] = ["modules.json", "config.json", "rust_model.ot"];
+
+/// Returns whether `model_dir` contains every file in [`MODEL_FILES`].
+///
+/// An empty or partially downloaded directory counts as not installed.
+pub fn is_model_installed(model_dir: &Path) -> bool {
+    MODEL_FILES
+        .iter()
+        .all(|file| model_dir.join(file).is_file())
+}
+
+/// Generates the embedding of `input_text` with the model in the config directory.
+pub fn generate_embedding(input_text: &str) -> Result<Vec<Vec<f32>>, AiError> {
     // Load configuration from ncy.yaml in the config directory.
     let _config = get_config()?;
-    let config_dir = get_config_dir();
 
     // Determine the model directory.
-    // Since the new AIConfig does not include a model name, we default to "paraphrase-albert-small-v2".
-    // let model_dir: PathBuf = config_dir.join("paraphrase-albert-small-v2");
-    let model_dir: PathBuf = config_dir.join("all-MiniLM-L6-v2");
+    let model_dir: PathBuf = get_model_dir();
+
+    generate_embedding_with_model(&model_dir, input_text)
+}
 
-    // Build the model from the computed directory.
-    let model =
-        SentenceEmbeddingsBuilder::local(model_dir.to_str().ok_or("Invalid model directory path")?)
-            .with_device(tch::Device::cuda_if_available())
-            .create_model()?;
+/// Like [`generate_embedding`], but loads the model from `model_dir` instead of the config
+/// directory, e.g. a model bundled next to the binary. No configuration file is required.
+pub fn generate_embedding_with_model(
+    model_dir: &Path,
+    input_text: &str,
+) -> Result<Vec<Vec<f32>>, AiError> {
+    let model = load_model(model_dir)?;
 
     // Generate embeddings for the provided input text.
-    let sentences = [input_text];
-    let embeddings = model.encode(&sentences)?;
+    encode(&model, &[input_text])
+}
+
+/// Returns the native output dimension of the configured model.
+///
+/// Size embedding tables with this rather than assuming a dimension, so switching models
+/// can't silently mix vectors of different sizes.
+pub fn embedding_dim() -> Result<usize, AiError> {
+    embedding_dim_with_model(&get_model_dir())
+}
+
+/// Returns the native output dimension of the model in `model_dir`.
+///
+/// The dimension is inferred from a single encode, which works for every model the
+/// sentence-embeddings pipeline can load.
+pub fn embedding_dim_with_model(model_dir: &Path) -> Result<usize, AiError> {
+    let model = load_model(model_dir)?;
+    let embeddings = encode(&model, &["dimension probe"])?;
+    let first = embeddings.first().ok_or(AiError::NoOutput)?;
+    Ok(first.len())
+}
+
+/// Loads the sentence-embeddings model from `model_dir`, on the GPU if one is available.
+///
+/// Returns `ModelNotFound` unless the model is installed (see [`is_model_installed`]).
+pub(crate) fn load_model(model_dir: &Path) -> Result<SentenceEmbeddingsModel, AiError> {
+    let model_dir_str = model_dir
+        .to_str()
+        .filter(|_| is_model_installed(model_dir))
+        .ok_or_else(|| AiError::ModelNotFound(model_dir.to_path_buf()))?;
+    SentenceEmbeddingsBuilder::local(model_dir_str)
+        .with_device(tch::Device::cuda_if_available())
+        .create_model()
+        .map_err(AiError::ModelLoad)
+}
+
+/// Encodes `texts` with `model`, one embedding per text.
+///
+/// Device failures are told apart from other encode failures on a best-effort basis:
+/// libtorch reports both as plain tensor error messages, so a failure counts as a device
+/// error only if its message contains one of [`DEVICE_ERROR_MARKERS`].
+pub(crate) fn encode(
+    model: &SentenceEmbeddingsModel,
+    texts: &[&str],
+) -> Result<Vec<Vec<f32>>, AiError> {
+    model.encode(texts).map_err(|e| match &e {
+        RustBertError::TchError(message) if is_device_error(message) => AiError::Device(e),
+        _ => AiError::Encode(e),
+    })
+}
+
+/// Substrings of libtorch error messages that indicate a GPU failure rather than bad input.
+const DEVICE_ERROR_MARKERS: [&str; 5] = [
+    "CUDA out of memory",
+    "CUDA error",
+    "CUBLAS_STATUS",
+    "cuDNN error",
+    "no CUDA GPUs are available",
+];
+
+fn is_device_error(message: &str) -> bool {
+    DEVICE_ERROR_MARKERS
+        .iter()
+        .any(|marker| message.contains(marker))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_model_dir_is_model_not_found() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let missing = dir.path().join("no-such-model");
+        let result = generate_embedding_with_model(&missing, "text");
+        assert!(matches!(result, Err(AiError::ModelNotFound(path)) if path == missing));
+    }
+
+    #[test]
+    fn test_partial_model_dir_is_model_not_found() {
+        let dir = tempfile::TempDir::new().unwrap();
+        // An empty directory, as left behind before the model is downloaded.
+        assert!(!is_model_installed(dir.path()));
+        assert!(matches!(
+            generate_embedding_with_model(dir.path(), "text"),
+            Err(AiError::ModelNotFound(_))
+        ));
+
+        std::fs::write(dir.path().join("config.json"), "{}").unwrap();
+        assert!(!is_model_installed(dir.path()));
+        for file in MODEL_FILES {
+            std::fs::write(dir.path().join(file), "").unwrap();
+        }
+        assert!(is_model_installed(dir.path()));
+    }
 
-    Ok(embeddings)
+    #[test]
+    fn test_is_device_error() {
+        assert!(is_device_error(
+            "CUDA out of memory. Tried to allocate 20.00 MiB"
+        ));
+        assert!(!is_device_error("Expected input to be on CUDA device"));
+        assert!(!is_device_error("index out of range in self"));
+    }
 }
@@ -1,6 +1,6 @@
 use std::fs;
 use std::io;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use serde::{Deserialize, Serialize};
 use serde_yaml;
@@ -16,6 +16,8 @@ pub enum ConfigError {
     EmptyConfig,
     /// Returned when required keys/values are missing.
     InvalidConfig(String),
+    /// Returned when the nlprule tokenizer binary is not present at the given path.
+    MissingTokenizer(PathBuf),
 }
 
 impl std::fmt::Display for ConfigError {
@@ -28,6 +30,13 @@ impl std::fmt::Display for ConfigError {
             }
             ConfigError::EmptyConfig => write!(f, "Config file is empty"),
             ConfigError::InvalidConfig(msg) => write!(f, "Invalid config: {}", msg),
+            ConfigError::MissingTokenizer(path) => write!(
+                f,
+                "nlprule tokenizer not found at {}. Download it from {}, decompress it to that path, \
+                 or call ai::nlputils::download_tokenizer()",
+                path.display(),
+                crate::ai::nlputils::TOKENIZER_DOWNLOAD_URL
+            ),
         }
     }
 }
@@ -56,11 +65,49 @@ pub fn get_config_dir() -> PathBuf {
     home.join(".config").join("notemancy")
 }
 
+/// Environment variable selecting a config profile.
+pub const PROFILE_ENV_VAR: &str = "NOTEMANCY_PROFILE";
+
+/// Returns the config file name for a profile.
+///
+/// Without a profile (or with an empty one) this is `ncy.yaml`; with profile `dev` it is `ncy.dev.yaml`.
+///
+/// # Errors
+///
+/// Returns `InvalidConfig` if the profile contains anything but ASCII letters, digits, `_`
+/// and `-`, so a profile can't point outside the config directory.
+pub fn config_file_name(profile: Option<&str>) -> Result<String, ConfigError> {
+    match profile.map(str::trim).filter(|p| !p.is_empty()) {
+        Some(profile)
+            if profile
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') =>
+        {
+            Ok(format!("ncy.{}.yaml", profile))
+        }
+        Some(profile) => Err(ConfigError::InvalidConfig(format!(
+            "Invalid profile name '{}': use only letters, digits, '_' and '-'",
+            profile
+        ))),
+        None => Ok("ncy.yaml".to_string()),
+    }
+}
+
 /// Computes the full path to the config file.
-pub fn get_config_file_path() -> PathBuf {
+///
+/// The profile is read from the `NOTEMANCY_PROFILE` environment variable, so
+/// `NOTEMANCY_PROFILE=dev` selects `ncy.dev.yaml`. Without it, `ncy.yaml` is used.
+/// Fails if the profile is invalid (see [`config_file_name`]).
+pub fn get_config_file_path() -> Result<PathBuf, ConfigError> {
+    let profile = std::env::var(PROFILE_ENV_VAR).ok();
+    get_config_file_path_for_profile(profile.as_deref())
+}
+
+/// Computes the full path to the config file for an explicit profile.
+pub fn get_config_file_path_for_profile(profile: Option<&str>) -> Result<PathBuf, ConfigError> {
     let mut path = get_config_dir();
-    path.push("ncy.yaml");
-    path
+    path.push(config_file_name(profile)?);
+    Ok(path)
 }
 
 /// Represents the whole configuration.
@@ -75,12 +122,42 @@ pub struct Config {
 pub struct AIConfig {
     pub semantic_thresh: Option<f64>,
     pub autotagging: Option<AutoTaggingConfig>,
+    /// Optional embeddings store settings.
+    pub embeddings: Option<EmbeddingsConfig>,
+    /// Optional embedding provider settings; the local model is used when absent.
+    pub embedder: Option<EmbedderConfig>,
 }
 
 /// Represents the autotagging configuration.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AutoTaggingConfig {
     pub mode: Option<String>,
+    /// Candidate phrase extractor: `nlprule` (default) or `simple`, which needs no tokenizer file.
+    pub extractor: Option<String>,
+    /// Longest candidate phrase, in words (default 2).
+    pub max_ngram: Option<usize>,
+}
+
+/// Represents the embeddings store configuration.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EmbeddingsConfig {
+    /// Prefix prepended to the embeddings table name, e.g. `work` gives `work_embeddings`.
+    pub table_prefix: Option<String>,
+}
+
+/// Represents the embedding provider configuration.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EmbedderConfig {
+    /// `local` (default) or `http` for an OpenAI `/embeddings`-compatible service.
+    pub provider: Option<String>,
+    /// Full URL of the embeddings endpoint, for `http`.
+    pub endpoint: Option<String>,
+    /// Model name sent to the service, for `http`.
+    pub model: Option<String>,
+    /// Dimension of the vectors the service returns, for `http`.
+    pub dimension: Option<usize>,
+    /// Name of the environment variable holding the API key, for `http`.
+    pub api_key_env: Option<String>,
 }
 
 /// Checks whether the configuration file exists and validates its content.
@@ -95,18 +172,23 @@ pub struct AutoTaggingConfig {
 /// Returns a `ConfigError` if any I/O or deserialization error occurs, or if required
 /// keys/values are missing.
 pub fn validate_config() -> Result<(), ConfigError> {
-    let config_path = get_config_file_path();
+    validate_config_at(&get_config_file_path()?)
+}
 
+/// Validates the configuration file at `config_path` instead of the default location.
+///
+/// Behaves exactly like [`validate_config`], including creating an empty file when missing.
+pub fn validate_config_at(config_path: &Path) -> Result<(), ConfigError> {
     // Check if config file exists; if not, create it (and parent directories) as empty.
     if !config_path.exists() {
         if let Some(parent) = config_path.parent() {
             fs::create_dir_all(parent).map_err(ConfigError::IoError)?;
         }
-        fs::write(&config_path, "").map_err(ConfigError::IoError)?;
+        fs::write(config_path, "").map_err(ConfigError::IoError)?;
         return Err(ConfigError::MissingConfig);
     }
 
-    let content = fs::read_to_string(&config_path).map_err(ConfigError::IoError)?;
+    let content = fs::read_to_string(config_path).map_err(ConfigError::IoError)?;
     if content.trim().is_empty() {
         return Err(ConfigError::EmptyConfig);
     }
@@ -152,8 +234,16 @@ pub fn validate_config() -> Result<(), ConfigError> {
 ///
 /// Returns a `ConfigError` if any I/O or deserialization error occurs, or if the file is empty.
 pub fn get_config() -> Result<Config, ConfigError> {
-    let config_path = get_config_file_path();
-    let content = fs::read_to_string(&config_path).map_err(ConfigError::IoError)?;
+    get_config_from(&get_config_file_path()?)
+}
+
+/// Parses the configuration file at `config_path` instead of the default location.
+///
+/// # Errors
+///
+/// Returns a `ConfigError` if any I/O or deserialization error occurs, or if the file is empty.
+pub fn get_config_from(config_path: &Path) -> Result<Config, ConfigError> {
+    let content = fs::read_to_string(config_path).map_err(ConfigError::IoError)?;
     if content.trim().is_empty() {
         return Err(ConfigError::EmptyConfig);
     }
@@ -163,6 +253,7 @@ pub fn get_config() -> Result<Config, ConfigError> {
 
 #[cfg(test)]
 mod tests {
+    use super::{get_config_from, validate_config_at, ConfigError};
     use std::fs;
     use std::path::PathBuf;
     use tempfile::TempDir;
@@ -175,13 +266,41 @@ mod tests {
         (temp_dir, config_dir)
     }
 
+    #[test]
+    fn test_config_file_name_for_profiles() {
+        assert_eq!(super::config_file_name(None).unwrap(), "ncy.yaml");
+        assert_eq!(super::config_file_name(Some("")).unwrap(), "ncy.yaml");
+        assert_eq!(
+            super::config_file_name(Some("dev")).unwrap(),
+            "ncy.dev.yaml"
+        );
+    }
+
+    #[test]
+    fn test_config_file_name_rejects_path_profiles() {
+        for profile in ["../other", "a/b", "a\\b", "..", "dev.test"] {
+            assert!(
+                matches!(
+                    super::config_file_name(Some(profile)),
+                    Err(ConfigError::InvalidConfig(_))
+                ),
+                "profile {:?} was accepted",
+                profile
+            );
+        }
+    }
+
     /// A test for `validate_config` when the config file is missing.
     #[test]
     fn test_validate_config_missing() {
-        let (_temp_dir, _config_dir) = setup_temp_config_dir();
-        // For testing purposes, refactor your functions to accept a custom config path.
-        // Use something like: let result = validate_config_at(&config_dir.join("ncy.yaml"));
-        // and assert that the error matches ConfigError::MissingConfig.
+        let (_temp_dir, config_dir) = setup_temp_config_dir();
+        let config_path = config_dir.join("ncy.yaml");
+        let result = validate_config_at(&config_path);
+        assert!(matches!(result, Err(ConfigError::MissingConfig)));
+        // The missing file is created empty, so a second validation reports it as empty.
+        assert!(config_path.exists());
+        let result = validate_config_at(&config_path);
+        assert!(matches!(result, Err(ConfigError::EmptyConfig)));
     }
 
     /// A test for `get_config` when the config file is empty.
@@ -193,8 +312,23 @@ mod tests {
         // Create an empty config file.
         fs::write(&config_path, "").expect("Failed to write empty config file");
 
-        // For testing, use a function that accepts a config path.
-        // e.g., let result = get_config_from(&config_path);
-        // assert!(matches!(result, Err(ConfigError::EmptyConfig)));
+        let result = get_config_from(&config_path);
+        assert!(matches!(result, Err(ConfigError::EmptyConfig)));
+    }
+
+    /// A test for `get_config_from` reading a caller-specified file.
+    #[test]
+    fn test_get_config_from_path() {
+        let (_temp_dir, config_dir) = setup_temp_config_dir();
+        let config_path = config_dir.join("custom.yaml");
+        fs::write(
+            &config_path,
+            "vault_dir: /tmp/vault\nai:\n  semantic_thresh: 0.5\n  autotagging:\n    mode: semantic\n",
+        )
+        .expect("Failed to write config file");
+
+        let config = get_config_from(&config_path).unwrap();
+        assert_eq!(config.vault_dir, Some(PathBuf::from("/tmp/vault")));
+        assert!(validate_config_at(&config_path).is_ok());
     }
 }
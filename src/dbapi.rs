@@ -1,7 +1,12 @@
 use crate::confapi::get_config_dir;
-use rusqlite::{params, Connection};
+use crate::utils::normalize_path;
+use chrono::{DateTime, SecondsFormat, Utc};
+use log::warn;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 
 /// Directory name for the database files.
 pub const DB_DIR_NAME: &str = "database";
@@ -13,6 +18,9 @@ pub const DB_FILE_NAME: &str = "pagetable.sqlite";
 pub enum DbError {
     Io(std::io::Error),
     Sqlite(rusqlite::Error),
+    /// The unique vpath index could not be created because several records share a vpath.
+    /// Each entry is a duplicated vpath and the lpaths of the records using it.
+    DuplicateVpaths(Vec<(String, Vec<String>)>),
 }
 
 impl std::fmt::Display for DbError {
@@ -20,6 +28,13 @@ impl std::fmt::Display for DbError {
         match self {
             DbError::Io(e) => write!(f, "IO Error: {}", e),
             DbError::Sqlite(e) => write!(f, "SQLite Error: {}", e),
+            DbError::DuplicateVpaths(duplicates) => {
+                write!(f, "Cannot make vpath unique, these records share a vpath:")?;
+                for (vpath, lpaths) in duplicates {
+                    write!(f, "\n  {}: {}", vpath, lpaths.join(", "))?;
+                }
+                Ok(())
+            }
         }
     }
 }
@@ -61,15 +76,43 @@ pub fn check_db_path() -> Result<(), DbError> {
     Ok(())
 }
 
-/// Runs automatic migrations on the database.
-/// First, it creates the `pagetable` table (if not present) with the new `project` column,
-/// and then it checks if the `project` column exists in an already existing table and adds it if missing.
+/// Opens the database file at `path` and runs migrations on it (see [`migrate`]).
+///
+/// The parent directory is created if needed. Use this instead of the config directory
+/// database (see [`get_db_file_path`]) when the caller chooses the location itself.
+pub fn open_db_at(path: &Path) -> Result<Connection, DbError> {
+    if let Some(parent) = path.parent() {
+        if !parent.as_os_str().is_empty() && !parent.exists() {
+            fs::create_dir_all(parent)?;
+        }
+    }
+    let conn = Connection::open(path)?;
+    migrate(&conn)?;
+    Ok(conn)
+}
+
+/// Runs automatic migrations on the database file (see [`migrate`]).
 pub fn run_migrations() -> Result<(), DbError> {
     // Ensure the database path is set up.
     check_db_path()?;
     let db_file_path = get_db_file_path();
     let conn = Connection::open(db_file_path)?;
+    migrate(&conn)
+}
 
+/// Runs automatic migrations on an open connection.
+/// First, it creates the `pagetable` table (if not present) with the new `project`, `content_hash` and `deleted_at` columns,
+/// and then it checks if those columns exist in an already existing table and adds them if missing.
+///
+/// Finally it creates a unique index on non-empty vpaths (see [`VPATH_INDEX_NAME`]). If
+/// records already share a vpath, the conflicts are logged once and the migration carries
+/// on without the index, so the duplicates can still be fixed through this API. Later
+/// migrations in the same process skip the index until [`ensure_unique_vpaths`] succeeds;
+/// call it to get the conflicts as an error, or to create the index once they are resolved.
+///
+/// Migrations are idempotent and work on any connection, including
+/// `Connection::open_in_memory()`, which keeps DB-layer tests hermetic.
+pub fn migrate(conn: &Connection) -> Result<(), DbError> {
     // Create the table if it does not exist.
     conn.execute(
         "CREATE TABLE IF NOT EXISTS pagetable (
@@ -78,30 +121,139 @@ pub fn run_migrations() -> Result<(), DbError> {
             title TEXT NOT NULL,
             timestamp TEXT NOT NULL,
             vpath TEXT NOT NULL,
-            project TEXT
+            project TEXT,
+            content_hash TEXT,
+            deleted_at TEXT
         )",
         [],
     )?;
 
     // Check if the 'project' column exists; if not, add it.
-    let mut stmt = conn.prepare("PRAGMA table_info(pagetable)")?;
-    let mut has_project = false;
-    let mut rows = stmt.query([])?;
-    while let Some(row) = rows.next()? {
-        let col_name: String = row.get("name")?;
-        if col_name == "project" {
-            has_project = true;
-            break;
-        }
-    }
-    if !has_project {
+    if !has_column(conn, "pagetable", "project")? {
         // Note: ALTER TABLE ADD COLUMN in SQLite cannot use "IF NOT EXISTS" so we check beforehand.
         conn.execute("ALTER TABLE pagetable ADD COLUMN project TEXT", [])?;
     }
 
+    // Check if the 'content_hash' column exists; if not, add it.
+    if !has_column(conn, "pagetable", "content_hash")? {
+        conn.execute("ALTER TABLE pagetable ADD COLUMN content_hash TEXT", [])?;
+    }
+
+    // Check if the 'deleted_at' column exists; if not, add it.
+    if !has_column(conn, "pagetable", "deleted_at")? {
+        conn.execute("ALTER TABLE pagetable ADD COLUMN deleted_at TEXT", [])?;
+    }
+
+    // Case-insensitive index backing title prefix lookups (see `autocomplete_titles`).
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS title_nocase ON pagetable (title COLLATE NOCASE)",
+        [],
+    )?;
+
+    // Once duplicates were reported, skip the check until `ensure_unique_vpaths` succeeds,
+    // so every call doesn't repeat the query and the warning.
+    if DUPLICATE_VPATHS_REPORTED.load(Ordering::Relaxed) {
+        return Ok(());
+    }
+    match create_vpath_index(conn) {
+        Err(e @ DbError::DuplicateVpaths(_)) => {
+            DUPLICATE_VPATHS_REPORTED.store(true, Ordering::Relaxed);
+            warn!(
+                "{}\nResolve them and call dbapi::ensure_unique_vpaths to create the index",
+                e
+            );
+            Ok(())
+        }
+        result => result,
+    }
+}
+
+/// Set once [`migrate`] has reported duplicate vpaths in this process.
+static DUPLICATE_VPATHS_REPORTED: AtomicBool = AtomicBool::new(false);
+
+/// Creates the unique vpath index (see [`VPATH_INDEX_NAME`]) if it is missing.
+///
+/// Returns [`DbError::DuplicateVpaths`] listing the conflicting records if several records
+/// share a non-empty vpath. Migrations only log that report, so use this to surface it,
+/// e.g. after resolving duplicates.
+pub fn ensure_unique_vpaths() -> Result<(), DbError> {
+    run_migrations()?;
+    let db_file_path = get_db_file_path();
+    let conn = Connection::open(db_file_path)?;
+    create_vpath_index(&conn)
+}
+
+/// Runs [`ensure_unique_vpaths`] on an open connection.
+///
+/// On success, later migrations check for duplicates again.
+fn create_vpath_index(conn: &Connection) -> Result<(), DbError> {
+    if !has_index(conn, VPATH_INDEX_NAME)? {
+        let duplicates = duplicate_vpaths(conn)?;
+        if !duplicates.is_empty() {
+            return Err(DbError::DuplicateVpaths(duplicates));
+        }
+        conn.execute(
+            &format!(
+                "CREATE UNIQUE INDEX {} ON pagetable (vpath) WHERE vpath <> ''",
+                VPATH_INDEX_NAME
+            ),
+            [],
+        )?;
+    }
+    DUPLICATE_VPATHS_REPORTED.store(false, Ordering::Relaxed);
     Ok(())
 }
 
+/// Name of the partial unique index on `pagetable.vpath`.
+///
+/// Empty vpaths are excluded, since records added for embeddings have none. While the
+/// index exists, inserting a record with an existing non-empty vpath fails. To reverse the
+/// migration, run `DROP INDEX vpath_unique`; it is recreated on the next migration unless
+/// duplicates exist by then.
+pub const VPATH_INDEX_NAME: &str = "vpath_unique";
+
+/// Returns whether an index named `name` exists.
+fn has_index(conn: &Connection, name: &str) -> Result<bool, DbError> {
+    let mut stmt =
+        conn.prepare("SELECT 1 FROM sqlite_master WHERE type = 'index' AND name = ?1")?;
+    Ok(stmt.exists(params![name])?)
+}
+
+/// Returns each non-empty vpath used by more than one record, with the lpaths using it.
+fn duplicate_vpaths(conn: &Connection) -> Result<Vec<(String, Vec<String>)>, DbError> {
+    let mut stmt = conn.prepare(
+        "SELECT vpath, lpath FROM pagetable
+         WHERE vpath <> '' AND vpath IN (
+             SELECT vpath FROM pagetable WHERE vpath <> '' GROUP BY vpath HAVING COUNT(*) > 1
+         )
+         ORDER BY vpath, lpath",
+    )?;
+    let mut rows = stmt.query([])?;
+    let mut duplicates: Vec<(String, Vec<String>)> = Vec::new();
+    while let Some(row) = rows.next()? {
+        let vpath: String = row.get(0)?;
+        let lpath: String = row.get(1)?;
+        match duplicates.last_mut() {
+            Some((last, lpaths)) if *last == vpath => lpaths.push(lpath),
+            _ => duplicates.push((vpath, vec![lpath])),
+        }
+    }
+    Ok(duplicates)
+}
+
+/// Returns whether `table` has a column named `column`.
+fn has_column(conn: &Connection, table: &str, column: &str) -> Result<bool, DbError> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let col_name: String = row.get("name")?;
+        if col_name == column {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
 /// A record to be inserted into the pagetable.
 #[derive(Debug)]
 pub struct Record {
@@ -111,6 +263,8 @@ pub struct Record {
     pub vpath: String,
     /// New optional field.
     pub project: Option<String>,
+    /// Hash of the file contents (see `utils::hash_content`), used for change detection.
+    pub content_hash: Option<String>,
 }
 
 /// Returned status for adding a record.
@@ -121,19 +275,48 @@ pub enum AddRecordStatus {
 }
 
 /// Inserts a new record into the pagetable.
-/// If a record with the same `lpath` already exists, the function returns `AlreadyExists`.
+/// The `lpath` is stored normalized (see `utils::normalize_path`).
+/// If a record with the same `lpath`, or the same non-empty `vpath`, already exists, the
+/// function returns `AlreadyExists`. A trashed record with the same `lpath` is restored
+/// with the new values instead, and counts as `Inserted`.
 pub fn add_record(record: &Record) -> Result<AddRecordStatus, DbError> {
     run_migrations()?;
     let db_file_path = get_db_file_path();
     let conn = Connection::open(db_file_path)?;
+    add_record_in(&conn, record)
+}
+
+/// Runs [`add_record`] on an open connection.
+pub(crate) fn add_record_in(
+    conn: &Connection,
+    record: &Record,
+) -> Result<AddRecordStatus, DbError> {
+    let restored = conn.execute(
+        "UPDATE pagetable
+         SET title = ?1, timestamp = ?2, vpath = ?3, project = ?4, content_hash = ?5, deleted_at = NULL
+         WHERE lpath IN (?6, ?7) AND deleted_at IS NOT NULL",
+        params![
+            record.title,
+            record.timestamp,
+            record.vpath,
+            record.project,
+            record.content_hash,
+            normalize_path(&record.lpath),
+            record.lpath
+        ],
+    )?;
+    if restored > 0 {
+        return Ok(AddRecordStatus::Inserted);
+    }
     let count = conn.execute(
-        "INSERT OR IGNORE INTO pagetable (lpath, title, timestamp, vpath, project) VALUES (?1, ?2, ?3, ?4, ?5)",
+        "INSERT OR IGNORE INTO pagetable (lpath, title, timestamp, vpath, project, content_hash) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
         params![
-            record.lpath,
+            normalize_path(&record.lpath),
             record.title,
             record.timestamp,
             record.vpath,
-            record.project
+            record.project,
+            record.content_hash
         ],
     )?;
     if count == 0 {
@@ -158,6 +341,7 @@ pub struct RecordUpdate {
     pub vpath: Option<String>,
     /// New optional update field.
     pub project: Option<String>,
+    pub content_hash: Option<String>,
 }
 
 /// Updates a record in the `pagetable`.
@@ -167,14 +351,22 @@ pub fn update_record(identifier: RecordIdentifier, update: RecordUpdate) -> Resu
     run_migrations()?;
     let db_file_path = get_db_file_path();
     let conn = Connection::open(db_file_path)?;
+    update_record_in(&conn, identifier, update)
+}
 
+/// Runs [`update_record`] on an open connection.
+pub(crate) fn update_record_in(
+    conn: &Connection,
+    identifier: RecordIdentifier,
+    update: RecordUpdate,
+) -> Result<(), DbError> {
     let mut query = "UPDATE pagetable SET ".to_string();
     let mut clauses = Vec::new();
     let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
 
     if let Some(new_lpath) = update.lpath {
         clauses.push("lpath = ?");
-        params.push(Box::new(new_lpath));
+        params.push(Box::new(normalize_path(&new_lpath)));
     }
     if let Some(new_title) = update.title {
         clauses.push("title = ?");
@@ -192,6 +384,10 @@ pub fn update_record(identifier: RecordIdentifier, update: RecordUpdate) -> Resu
         clauses.push("project = ?");
         params.push(Box::new(new_project));
     }
+    if let Some(new_content_hash) = update.content_hash {
+        clauses.push("content_hash = ?");
+        params.push(Box::new(new_content_hash));
+    }
 
     if clauses.is_empty() {
         // Nothing to update.
@@ -206,7 +402,9 @@ pub fn update_record(identifier: RecordIdentifier, update: RecordUpdate) -> Resu
             params.push(Box::new(id));
         }
         RecordIdentifier::Lpath(lpath) => {
-            query.push_str(" WHERE lpath = ?");
+            // Match the normalized form as well as the raw path, for rows stored before normalization.
+            query.push_str(" WHERE lpath IN (?, ?)");
+            params.push(Box::new(normalize_path(&lpath)));
             params.push(Box::new(lpath));
         }
     }
@@ -222,21 +420,479 @@ pub fn delete_record(identifier: RecordIdentifier) -> Result<(), DbError> {
     run_migrations()?;
     let db_file_path = get_db_file_path();
     let conn = Connection::open(db_file_path)?;
-    let (query, param): (&str, Box<dyn rusqlite::ToSql>) = match identifier {
-        RecordIdentifier::Id(id) => ("DELETE FROM pagetable WHERE id = ?", Box::new(id)),
+    delete_record_in(&conn, identifier)
+}
+
+/// Runs [`delete_record`] on an open connection.
+pub(crate) fn delete_record_in(
+    conn: &Connection,
+    identifier: RecordIdentifier,
+) -> Result<(), DbError> {
+    match identifier {
+        RecordIdentifier::Id(id) => {
+            conn.execute("DELETE FROM pagetable WHERE id = ?", params![id])?;
+        }
         RecordIdentifier::Lpath(lpath) => {
-            ("DELETE FROM pagetable WHERE lpath = ?", Box::new(lpath))
+            // Match the normalized form as well as the raw path, for rows stored before normalization.
+            conn.execute(
+                "DELETE FROM pagetable WHERE lpath IN (?1, ?2)",
+                params![normalize_path(&lpath), lpath],
+            )?;
         }
-    };
-    conn.execute(query, params![param])?;
+    }
     Ok(())
 }
 
+/// Moves a record to the trash instead of deleting it.
+///
+/// Trashed records keep their row, with `deleted_at` set to the current time, but are
+/// left out of lookups and listings until they are restored with [`restore_record`] or
+/// removed for good with [`purge_deleted`]. Trashing an already trashed record keeps its
+/// original `deleted_at`.
+pub fn soft_delete_record(identifier: RecordIdentifier) -> Result<(), DbError> {
+    run_migrations()?;
+    let db_file_path = get_db_file_path();
+    let conn = Connection::open(db_file_path)?;
+    soft_delete_record_in(&conn, identifier)
+}
+
+/// Runs [`soft_delete_record`] on an open connection.
+pub(crate) fn soft_delete_record_in(
+    conn: &Connection,
+    identifier: RecordIdentifier,
+) -> Result<(), DbError> {
+    let now = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
+    match identifier {
+        RecordIdentifier::Id(id) => {
+            conn.execute(
+                "UPDATE pagetable SET deleted_at = ?1 WHERE id = ?2 AND deleted_at IS NULL",
+                params![now, id],
+            )?;
+        }
+        RecordIdentifier::Lpath(lpath) => {
+            conn.execute(
+                "UPDATE pagetable SET deleted_at = ?1 WHERE lpath IN (?2, ?3) AND deleted_at IS NULL",
+                params![now, normalize_path(&lpath), lpath],
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Restores a trashed record (see [`soft_delete_record`]).
+/// Returns whether a trashed record was found and restored.
+pub fn restore_record(identifier: RecordIdentifier) -> Result<bool, DbError> {
+    run_migrations()?;
+    let db_file_path = get_db_file_path();
+    let conn = Connection::open(db_file_path)?;
+    restore_record_in(&conn, identifier)
+}
+
+/// Runs [`restore_record`] on an open connection.
+pub(crate) fn restore_record_in(
+    conn: &Connection,
+    identifier: RecordIdentifier,
+) -> Result<bool, DbError> {
+    let restored = match identifier {
+        RecordIdentifier::Id(id) => conn.execute(
+            "UPDATE pagetable SET deleted_at = NULL WHERE id = ?1 AND deleted_at IS NOT NULL",
+            params![id],
+        )?,
+        RecordIdentifier::Lpath(lpath) => conn.execute(
+            "UPDATE pagetable SET deleted_at = NULL WHERE lpath IN (?1, ?2) AND deleted_at IS NOT NULL",
+            params![normalize_path(&lpath), lpath],
+        )?,
+    };
+    Ok(restored > 0)
+}
+
+/// Permanently deletes records that were trashed before `cutoff` and returns their lpaths.
+pub fn purge_deleted(cutoff: DateTime<Utc>) -> Result<Vec<String>, DbError> {
+    run_migrations()?;
+    let db_file_path = get_db_file_path();
+    let conn = Connection::open(db_file_path)?;
+    // `deleted_at` is always written in the same UTC RFC 3339 format, so it sorts as text.
+    let cutoff = cutoff.to_rfc3339_opts(SecondsFormat::Secs, true);
+    let mut stmt = conn.prepare("SELECT lpath FROM pagetable WHERE deleted_at < ?1")?;
+    let lpaths = stmt
+        .query_map(params![cutoff], |row| row.get(0))?
+        .collect::<Result<Vec<String>, _>>()?;
+    conn.execute(
+        "DELETE FROM pagetable WHERE deleted_at < ?1",
+        params![cutoff],
+    )?;
+    Ok(lpaths)
+}
+
+/// Returns the record stored for `lpath`, comparing both its raw and normalized form.
+/// Trashed records are not returned.
+pub fn get_record(lpath: &str) -> Result<Option<Record>, DbError> {
+    run_migrations()?;
+    let db_file_path = get_db_file_path();
+    let conn = Connection::open(db_file_path)?;
+    get_record_in(&conn, lpath)
+}
+
+/// Runs [`get_record`] on an open connection.
+pub(crate) fn get_record_in(conn: &Connection, lpath: &str) -> Result<Option<Record>, DbError> {
+    let record = conn
+        .query_row(
+            &format!(
+                "SELECT {} FROM pagetable WHERE lpath IN (?1, ?2) AND deleted_at IS NULL",
+                RECORD_COLUMNS
+            ),
+            params![normalize_path(lpath), lpath],
+            record_from_row,
+        )
+        .optional()?;
+    Ok(record)
+}
+
+/// Returns up to `limit` records whose title starts with `prefix`, ignoring ASCII case.
+///
+/// Suggestions are ranked shortest title first, then alphabetically, so the closest
+/// completion comes first. `%` and `_` in `prefix` match literally.
+pub fn autocomplete_titles(prefix: &str, limit: usize) -> Result<Vec<Record>, DbError> {
+    run_migrations()?;
+    let db_file_path = get_db_file_path();
+    let conn = Connection::open(db_file_path)?;
+    titles_with_prefix(&conn, prefix, limit)
+}
+
+/// Runs the [`autocomplete_titles`] query on an open connection.
+fn titles_with_prefix(
+    conn: &Connection,
+    prefix: &str,
+    limit: usize,
+) -> Result<Vec<Record>, DbError> {
+    let pattern = format!(
+        "{}%",
+        prefix
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_")
+    );
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM pagetable WHERE title LIKE ?1 ESCAPE '\\' AND deleted_at IS NULL
+         ORDER BY length(title), title COLLATE NOCASE LIMIT ?2",
+        RECORD_COLUMNS
+    ))?;
+    let records = stmt
+        .query_map(params![pattern, limit as i64], record_from_row)?
+        .collect::<Result<Vec<Record>, _>>()?;
+    Ok(records)
+}
+
+/// Groups records whose `content_hash` is identical, for reviewing duplicate notes.
+///
+/// Only groups of two or more records are returned, largest group first. Records
+/// without a stored hash are ignored.
+pub fn find_duplicate_content() -> Result<Vec<Vec<Record>>, DbError> {
+    run_migrations()?;
+    let db_file_path = get_db_file_path();
+    let conn = Connection::open(db_file_path)?;
+    duplicate_content(&conn)
+}
+
+/// Runs the [`find_duplicate_content`] query on an open connection.
+fn duplicate_content(conn: &Connection) -> Result<Vec<Vec<Record>>, DbError> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM pagetable
+         WHERE deleted_at IS NULL AND content_hash IN (
+             SELECT content_hash FROM pagetable WHERE content_hash IS NOT NULL AND deleted_at IS NULL
+             GROUP BY content_hash HAVING COUNT(*) > 1
+         )
+         ORDER BY content_hash, lpath",
+        RECORD_COLUMNS
+    ))?;
+    let mut groups: Vec<Vec<Record>> = Vec::new();
+    for record in stmt.query_map([], record_from_row)? {
+        let record = record?;
+        match groups.last_mut() {
+            Some(group) if group[0].content_hash == record.content_hash => group.push(record),
+            _ => groups.push(vec![record]),
+        }
+    }
+    // Stable sort, so groups of equal size stay ordered by hash.
+    groups.sort_by(|a, b| b.len().cmp(&a.len()));
+    Ok(groups)
+}
+
+/// Returns the records whose `timestamp` lies between `start` and `end` (inclusive),
+/// oldest first, optionally restricted to `project`.
+///
+/// Timestamps are compared as points in time, so any RFC 3339 offset works. Records whose
+/// timestamp can't be parsed as a date are left out.
+pub fn records_modified_between(
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    project: Option<&str>,
+) -> Result<Vec<Record>, DbError> {
+    run_migrations()?;
+    let db_file_path = get_db_file_path();
+    let conn = Connection::open(db_file_path)?;
+    modified_between(&conn, start, end, project)
+}
+
+/// Runs the [`records_modified_between`] query on an open connection.
+fn modified_between(
+    conn: &Connection,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    project: Option<&str>,
+) -> Result<Vec<Record>, DbError> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM pagetable
+         WHERE deleted_at IS NULL
+           AND julianday(timestamp) BETWEEN julianday(?1) AND julianday(?2)
+           AND (?3 IS NULL OR project = ?3)
+         ORDER BY julianday(timestamp)",
+        RECORD_COLUMNS
+    ))?;
+    let records = stmt
+        .query_map(
+            params![start.to_rfc3339(), end.to_rfc3339(), project],
+            record_from_row,
+        )?
+        .collect::<Result<Vec<Record>, _>>()?;
+    Ok(records)
+}
+
+/// Sort order for [`get_records_ordered`].
+///
+/// Each variant maps to a fixed `ORDER BY` clause, so no caller input reaches the SQL.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RecordOrder {
+    /// By virtual path, A to Z.
+    #[default]
+    VpathAsc,
+    /// By local path, A to Z.
+    LpathAsc,
+    /// By title, A to Z, ignoring ASCII case.
+    TitleAsc,
+    /// By timestamp, oldest first.
+    TimestampAsc,
+    /// By timestamp, newest first (e.g. for a recent-files view).
+    TimestampDesc,
+}
+
+impl RecordOrder {
+    /// Returns the `ORDER BY` clause for this order.
+    ///
+    /// Ties are broken by `lpath` so the result is deterministic.
+    fn order_by(self) -> &'static str {
+        match self {
+            RecordOrder::VpathAsc => "vpath, lpath",
+            RecordOrder::LpathAsc => "lpath",
+            RecordOrder::TitleAsc => "title COLLATE NOCASE, lpath",
+            RecordOrder::TimestampAsc => "julianday(timestamp), lpath",
+            RecordOrder::TimestampDesc => "julianday(timestamp) DESC, lpath",
+        }
+    }
+}
+
+/// Returns all records sorted by `order`, so clients don't have to re-sort in memory.
+///
+/// Records whose timestamp can't be parsed as a date sort last with
+/// [`RecordOrder::TimestampDesc`] and first with [`RecordOrder::TimestampAsc`].
+pub fn get_records_ordered(order: RecordOrder) -> Result<Vec<Record>, DbError> {
+    run_migrations()?;
+    let db_file_path = get_db_file_path();
+    let conn = Connection::open(db_file_path)?;
+    records_ordered(&conn, order)
+}
+
+/// Runs the [`get_records_ordered`] query on an open connection.
+fn records_ordered(conn: &Connection, order: RecordOrder) -> Result<Vec<Record>, DbError> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM pagetable WHERE deleted_at IS NULL ORDER BY {}",
+        RECORD_COLUMNS,
+        order.order_by()
+    ))?;
+    let records = stmt
+        .query_map([], record_from_row)?
+        .collect::<Result<Vec<Record>, _>>()?;
+    Ok(records)
+}
+
+/// Columns selected by queries that return whole records, in [`record_from_row`] order.
+const RECORD_COLUMNS: &str = "lpath, title, timestamp, vpath, project, content_hash";
+
+/// Builds a [`Record`] from a row selecting [`RECORD_COLUMNS`].
+fn record_from_row(row: &rusqlite::Row) -> rusqlite::Result<Record> {
+    Ok(Record {
+        lpath: row.get(0)?,
+        title: row.get(1)?,
+        timestamp: row.get(2)?,
+        vpath: row.get(3)?,
+        project: row.get(4)?,
+        content_hash: row.get(5)?,
+    })
+}
+
+/// Returns whether the file at `lpath` has changed since its record was stored.
+///
+/// `hash` is the file's current content hash (see `utils::hash_file`). A path with no
+/// record, a trashed record, or a record without a stored hash counts as changed.
+pub fn has_changed(lpath: &str, hash: &str) -> Result<bool, DbError> {
+    run_migrations()?;
+    let db_file_path = get_db_file_path();
+    let conn = Connection::open(db_file_path)?;
+    let stored: Option<Option<String>> = conn
+        .query_row(
+            "SELECT content_hash FROM pagetable WHERE lpath IN (?1, ?2) AND deleted_at IS NULL",
+            params![normalize_path(lpath), lpath],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(stored.flatten().as_deref() != Some(hash))
+}
+
+/// Counts the records in the pagetable, not counting trashed records.
+/// If `project` is given, only records belonging to that project are counted.
+pub fn count_records(project: Option<&str>) -> Result<usize, DbError> {
+    run_migrations()?;
+    let db_file_path = get_db_file_path();
+    let conn = Connection::open(db_file_path)?;
+    let count: i64 = match project {
+        Some(p) => conn.query_row(
+            "SELECT COUNT(*) FROM pagetable WHERE project = ?1 AND deleted_at IS NULL",
+            params![p],
+            |row| row.get(0),
+        )?,
+        None => conn.query_row(
+            "SELECT COUNT(*) FROM pagetable WHERE deleted_at IS NULL",
+            [],
+            |row| row.get(0),
+        )?,
+    };
+    Ok(count as usize)
+}
+
+/// Returns the distinct, non-NULL project names in the pagetable, sorted alphabetically.
+/// Projects whose records are all trashed are left out.
+pub fn list_projects() -> Result<Vec<String>, DbError> {
+    run_migrations()?;
+    let db_file_path = get_db_file_path();
+    let conn = Connection::open(db_file_path)?;
+    let mut stmt = conn.prepare(
+        "SELECT DISTINCT project FROM pagetable WHERE project IS NOT NULL AND deleted_at IS NULL ORDER BY project",
+    )?;
+    let projects = stmt
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<Vec<String>, _>>()?;
+    Ok(projects)
+}
+
+/// Deletes every record belonging to `project` and returns their lpaths.
+pub fn delete_project_records(project: &str) -> Result<Vec<String>, DbError> {
+    run_migrations()?;
+    let db_file_path = get_db_file_path();
+    let conn = Connection::open(db_file_path)?;
+    delete_project_records_in(&conn, project)
+}
+
+/// Runs [`delete_project_records`] on an open connection.
+pub(crate) fn delete_project_records_in(
+    conn: &Connection,
+    project: &str,
+) -> Result<Vec<String>, DbError> {
+    let lpaths = project_lpaths_in(conn, project)?;
+    conn.execute("DELETE FROM pagetable WHERE project = ?1", params![project])?;
+    Ok(lpaths)
+}
+
+/// Returns the lpaths of every record belonging to `project`, including trashed records.
+pub(crate) fn project_lpaths_in(conn: &Connection, project: &str) -> Result<Vec<String>, DbError> {
+    let mut stmt = conn.prepare("SELECT lpath FROM pagetable WHERE project = ?1")?;
+    let lpaths = stmt
+        .query_map(params![project], |row| row.get(0))?
+        .collect::<Result<Vec<String>, _>>()?;
+    Ok(lpaths)
+}
+
+/// Checks for many paths at once whether a record exists, like [`record_exists`].
+///
+/// All paths are checked on one connection with one `lpath IN (...)` query per
+/// [`MAX_QUERY_PARAMS`] lookups, so this is much cheaper than calling [`record_exists`]
+/// per path in bulk imports. The map is keyed by the paths as given.
+pub fn records_exist(paths: &[&str]) -> Result<HashMap<String, bool>, DbError> {
+    run_migrations()?;
+    let db_file_path = get_db_file_path();
+    let conn = Connection::open(db_file_path)?;
+    records_exist_in(&conn, paths)
+}
+
+/// Maximum number of parameters bound to a single statement, SQLite's historical
+/// default limit (`SQLITE_MAX_VARIABLE_NUMBER`).
+const MAX_QUERY_PARAMS: usize = 999;
+
+/// Runs [`records_exist`] on an open connection.
+pub(crate) fn records_exist_in(
+    conn: &Connection,
+    paths: &[&str],
+) -> Result<HashMap<String, bool>, DbError> {
+    // Look up the normalized and the raw form of every path, for rows stored before
+    // normalization.
+    let mut lookups: Vec<String> = paths
+        .iter()
+        .flat_map(|&path| [normalize_path(path), path.to_string()])
+        .collect();
+    lookups.sort();
+    lookups.dedup();
+
+    let mut found: HashSet<String> = HashSet::new();
+    for chunk in lookups.chunks(MAX_QUERY_PARAMS) {
+        let placeholders = vec!["?"; chunk.len()].join(", ");
+        let mut stmt = conn.prepare(&format!(
+            "SELECT lpath FROM pagetable WHERE deleted_at IS NULL AND lpath IN ({})",
+            placeholders
+        ))?;
+        let lpaths = stmt.query_map(rusqlite::params_from_iter(chunk), |row| row.get(0))?;
+        for lpath in lpaths {
+            found.insert(lpath?);
+        }
+    }
+
+    Ok(paths
+        .iter()
+        .map(|&path| {
+            let exists = found.contains(path) || found.contains(&normalize_path(path));
+            (path.to_string(), exists)
+        })
+        .collect())
+}
+
+/// Deletes the records stored under exactly the given lpaths, [`MAX_QUERY_PARAMS`] per
+/// statement, and returns how many rows were removed.
+///
+/// Unlike [`delete_record_in`] the lpaths are not normalized, so this is meant for lpaths
+/// read back from the pagetable.
+pub(crate) fn delete_lpaths_in(conn: &Connection, lpaths: &[String]) -> Result<usize, DbError> {
+    let mut deleted = 0;
+    for chunk in lpaths.chunks(MAX_QUERY_PARAMS) {
+        let placeholders = vec!["?"; chunk.len()].join(", ");
+        deleted += conn.execute(
+            &format!("DELETE FROM pagetable WHERE lpath IN ({})", placeholders),
+            rusqlite::params_from_iter(chunk),
+        )?;
+    }
+    Ok(deleted)
+}
+
+/// Returns whether a record exists for `path`, comparing both its raw and normalized form.
+/// Trashed records don't count, since [`add_record`] restores them.
 pub fn record_exists(path: &str) -> Result<bool, DbError> {
     let db_file_path = get_db_file_path();
     let conn = rusqlite::Connection::open(db_file_path)?;
-    let mut stmt = conn.prepare("SELECT 1 FROM pagetable WHERE lpath = ?1")?;
-    let exists = stmt.exists(rusqlite::params![path])?;
+    record_exists_in(&conn, path)
+}
+
+/// Runs [`record_exists`] on an open connection.
+pub(crate) fn record_exists_in(conn: &Connection, path: &str) -> Result<bool, DbError> {
+    let mut stmt =
+        conn.prepare("SELECT 1 FROM pagetable WHERE lpath IN (?1, ?2) AND deleted_at IS NULL")?;
+    let exists = stmt.exists(rusqlite::params![normalize_path(path), path])?;
     Ok(exists)
 }
 
@@ -253,28 +909,323 @@ mod tests {
     #[test]
     fn test_run_migrations_in_memory() {
         let conn = get_in_memory_connection();
-        // Run the migration query on the in-memory connection.
+        // Run the migrations on the in-memory connection, twice to check they are idempotent.
+        super::migrate(&conn).expect("Migration failed");
+        super::migrate(&conn).expect("Repeated migration failed");
+
+        let count: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='pagetable'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn test_migrate_adds_missing_project_column() {
+        let conn = get_in_memory_connection();
+        // A table created before the 'project' column existed.
         conn.execute(
-            "CREATE TABLE IF NOT EXISTS pagetable (
+            "CREATE TABLE pagetable (
                 id INTEGER PRIMARY KEY AUTOINCREMENT,
                 lpath TEXT UNIQUE NOT NULL,
                 title TEXT NOT NULL,
                 timestamp TEXT NOT NULL,
-                vpath TEXT NOT NULL,
-                project TEXT
+                vpath TEXT NOT NULL
             )",
             [],
         )
-        .expect("Migration failed");
+        .unwrap();
 
-        let count: i64 = conn
+        super::migrate(&conn).expect("Migration failed");
+        assert!(super::has_column(&conn, "pagetable", "project").unwrap());
+        assert!(super::has_column(&conn, "pagetable", "deleted_at").unwrap());
+    }
+
+    #[test]
+    fn test_migrate_tolerates_duplicate_vpaths() {
+        let conn = get_in_memory_connection();
+        // A table created before vpaths were unique.
+        conn.execute(
+            "CREATE TABLE pagetable (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                lpath TEXT UNIQUE NOT NULL,
+                title TEXT NOT NULL,
+                timestamp TEXT NOT NULL,
+                vpath TEXT NOT NULL
+            )",
+            [],
+        )
+        .unwrap();
+        for (lpath, vpath) in [
+            ("/a.md", "notes/a"),
+            ("/b.md", "notes/a"),
+            ("/c.md", "notes/a"),
+            ("/d.md", ""),
+            ("/e.md", ""),
+        ] {
+            conn.execute(
+                "INSERT INTO pagetable (lpath, title, timestamp, vpath) VALUES (?1, 't', 'ts', ?2)",
+                [lpath, vpath],
+            )
+            .unwrap();
+        }
+
+        // Migrating succeeds without the index, so the duplicates can be fixed.
+        super::migrate(&conn).expect("Migration failed");
+        assert!(!super::has_index(&conn, super::VPATH_INDEX_NAME).unwrap());
+        match super::create_vpath_index(&conn) {
+            Err(super::DbError::DuplicateVpaths(duplicates)) => assert_eq!(
+                duplicates,
+                vec![(
+                    "notes/a".to_string(),
+                    vec![
+                        "/a.md".to_string(),
+                        "/b.md".to_string(),
+                        "/c.md".to_string()
+                    ]
+                )]
+            ),
+            other => panic!("expected DuplicateVpaths, got {:?}", other),
+        }
+
+        super::update_record_in(
+            &conn,
+            super::RecordIdentifier::Lpath("/b.md".to_string()),
+            super::RecordUpdate {
+                vpath: Some("notes/b".to_string()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        super::delete_record_in(&conn, super::RecordIdentifier::Lpath("/c.md".to_string()))
+            .unwrap();
+
+        // Once resolved, the index is created and later duplicates are rejected.
+        super::create_vpath_index(&conn).expect("Creating the index failed");
+        assert!(super::has_index(&conn, super::VPATH_INDEX_NAME).unwrap());
+        assert!(conn
+            .execute(
+                "INSERT INTO pagetable (lpath, title, timestamp, vpath) VALUES ('/f.md', 't', 'ts', 'notes/a')",
+                [],
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_add_record_restores_trashed_record() {
+        let conn = get_in_memory_connection();
+        super::migrate(&conn).expect("Migration failed");
+        let record = |title: &str| super::Record {
+            lpath: "/note.md".to_string(),
+            title: title.to_string(),
+            timestamp: "2025-03-01T00:00:00Z".to_string(),
+            vpath: String::new(),
+            project: None,
+            content_hash: Some(title.to_string()),
+        };
+        assert!(matches!(
+            super::add_record_in(&conn, &record("old")).unwrap(),
+            super::AddRecordStatus::Inserted
+        ));
+        conn.execute(
+            "UPDATE pagetable SET deleted_at = '2025-03-02T00:00:00Z'",
+            [],
+        )
+        .unwrap();
+
+        // Re-adding a trashed record brings it back with the new values.
+        assert!(matches!(
+            super::add_record_in(&conn, &record("new")).unwrap(),
+            super::AddRecordStatus::Inserted
+        ));
+        let (title, deleted_at): (String, Option<String>) = conn
             .query_row(
-                "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='pagetable'",
+                "SELECT title, deleted_at FROM pagetable WHERE lpath = '/note.md'",
                 [],
-                |row| row.get(0),
+                |row| Ok((row.get(0)?, row.get(1)?)),
             )
             .unwrap();
-        assert_eq!(count, 1);
+        assert_eq!(title, "new");
+        assert_eq!(deleted_at, None);
+        assert!(matches!(
+            super::add_record_in(&conn, &record("newer")).unwrap(),
+            super::AddRecordStatus::AlreadyExists
+        ));
+    }
+
+    #[test]
+    fn test_records_exist_in_chunks() {
+        let conn = get_in_memory_connection();
+        super::migrate(&conn).expect("Migration failed");
+        let lpaths: Vec<String> = (0..1500).map(|i| format!("/notes/{}.md", i)).collect();
+        for lpath in lpaths.iter().step_by(2) {
+            conn.execute(
+                "INSERT INTO pagetable (lpath, title, timestamp, vpath) VALUES (?1, 't', 'ts', '')",
+                [lpath],
+            )
+            .unwrap();
+        }
+        conn.execute(
+            "UPDATE pagetable SET deleted_at = 'ts' WHERE lpath = '/notes/0.md'",
+            [],
+        )
+        .unwrap();
+
+        // More paths than fit in one statement.
+        let paths: Vec<&str> = lpaths.iter().map(String::as_str).collect();
+        let exists = super::records_exist_in(&conn, &paths).unwrap();
+        assert_eq!(exists.len(), 1500);
+        assert!(!exists["/notes/0.md"]);
+        assert!(!exists["/notes/1.md"]);
+        assert!(exists["/notes/2.md"]);
+        assert!(exists["/notes/1498.md"]);
+        assert!(!exists["/notes/1499.md"]);
+    }
+
+    #[test]
+    fn test_titles_with_prefix() {
+        let conn = get_in_memory_connection();
+        super::migrate(&conn).expect("Migration failed");
+        for (lpath, title) in [
+            ("/1.md", "Rust ownership"),
+            ("/2.md", "rust"),
+            ("/3.md", "Rusty nails"),
+            ("/4.md", "100% Rust"),
+            ("/5.md", "100 Rust tips"),
+        ] {
+            conn.execute(
+                "INSERT INTO pagetable (lpath, title, timestamp, vpath) VALUES (?1, ?2, 'ts', '')",
+                [lpath, title],
+            )
+            .unwrap();
+        }
+
+        let titles = |prefix: &str, limit: usize| -> Vec<String> {
+            super::titles_with_prefix(&conn, prefix, limit)
+                .unwrap()
+                .into_iter()
+                .map(|record| record.title)
+                .collect()
+        };
+        assert_eq!(
+            titles("RUS", 10),
+            vec!["rust", "Rusty nails", "Rust ownership"]
+        );
+        assert_eq!(titles("rus", 1), vec!["rust"]);
+        // Wildcards in the prefix match literally.
+        assert_eq!(titles("100%", 10), vec!["100% Rust"]);
+    }
+
+    #[test]
+    fn test_duplicate_content() {
+        let conn = get_in_memory_connection();
+        super::migrate(&conn).expect("Migration failed");
+        for (lpath, hash) in [
+            ("/a.md", Some("h1")),
+            ("/b.md", Some("h2")),
+            ("/c.md", Some("h2")),
+            ("/d.md", Some("h2")),
+            ("/e.md", Some("h1")),
+            ("/f.md", Some("h3")),
+            ("/g.md", None),
+            ("/h.md", None),
+        ] {
+            conn.execute(
+                "INSERT INTO pagetable (lpath, title, timestamp, vpath, content_hash) VALUES (?1, 't', 'ts', '', ?2)",
+                rusqlite::params![lpath, hash],
+            )
+            .unwrap();
+        }
+
+        let groups: Vec<Vec<String>> = super::duplicate_content(&conn)
+            .unwrap()
+            .into_iter()
+            .map(|group| group.into_iter().map(|record| record.lpath).collect())
+            .collect();
+        assert_eq!(
+            groups,
+            vec![vec!["/b.md", "/c.md", "/d.md"], vec!["/a.md", "/e.md"]]
+        );
+    }
+
+    #[test]
+    fn test_modified_between() {
+        let conn = get_in_memory_connection();
+        super::migrate(&conn).expect("Migration failed");
+        for (lpath, timestamp, project) in [
+            ("/old.md", "2025-01-01T10:00:00+00:00", None),
+            ("/week.md", "2025-03-05T12:30:00.123456+00:00", Some("work")),
+            ("/offset.md", "2025-03-06T01:00:00+02:00", None),
+            ("/garbage.md", "not a date", None),
+        ] {
+            conn.execute(
+                "INSERT INTO pagetable (lpath, title, timestamp, vpath, project) VALUES (?1, 't', ?2, '', ?3)",
+                rusqlite::params![lpath, timestamp, project],
+            )
+            .unwrap();
+        }
+
+        let lpaths = |project: Option<&str>| -> Vec<String> {
+            let start = "2025-03-01T00:00:00Z".parse().unwrap();
+            let end = "2025-03-07T00:00:00Z".parse().unwrap();
+            super::modified_between(&conn, start, end, project)
+                .unwrap()
+                .into_iter()
+                .map(|record| record.lpath)
+                .collect()
+        };
+        assert_eq!(lpaths(None), vec!["/week.md", "/offset.md"]);
+        assert_eq!(lpaths(Some("work")), vec!["/week.md"]);
+    }
+
+    #[test]
+    fn test_records_ordered() {
+        let conn = get_in_memory_connection();
+        super::migrate(&conn).expect("Migration failed");
+        for (lpath, title, timestamp, vpath) in [
+            ("/a.md", "beta", "2025-03-02T00:00:00Z", "/z"),
+            ("/b.md", "Alpha", "2025-03-03T00:00:00Z", "/y"),
+            ("/c.md", "gamma", "2025-03-01T00:00:00Z", "/x"),
+        ] {
+            conn.execute(
+                "INSERT INTO pagetable (lpath, title, timestamp, vpath) VALUES (?1, ?2, ?3, ?4)",
+                rusqlite::params![lpath, title, timestamp, vpath],
+            )
+            .unwrap();
+        }
+        conn.execute(
+            "INSERT INTO pagetable (lpath, title, timestamp, vpath, deleted_at)
+             VALUES ('/trashed.md', 'trashed', '2025-03-04T00:00:00Z', '/w', '2025-03-05T00:00:00Z')",
+            [],
+        )
+        .unwrap();
+
+        let lpaths = |order: super::RecordOrder| -> Vec<String> {
+            super::records_ordered(&conn, order)
+                .unwrap()
+                .into_iter()
+                .map(|record| record.lpath)
+                .collect()
+        };
+        assert_eq!(
+            lpaths(super::RecordOrder::default()),
+            vec!["/c.md", "/b.md", "/a.md"]
+        );
+        assert_eq!(
+            lpaths(super::RecordOrder::LpathAsc),
+            vec!["/a.md", "/b.md", "/c.md"]
+        );
+        assert_eq!(
+            lpaths(super::RecordOrder::TitleAsc),
+            vec!["/b.md", "/a.md", "/c.md"]
+        );
+        assert_eq!(
+            lpaths(super::RecordOrder::TimestampDesc),
+            vec!["/b.md", "/a.md", "/c.md"]
+        );
     }
 
     #[test]
@@ -0,0 +1,232 @@
+use crate::ai::nlputils::get_tokenizer_path;
+use crate::ai::sentence_transformer::{get_model_dir, is_model_installed};
+use crate::confapi::{get_config_dir, get_config_file_path, get_config_from};
+use crate::dbapi::get_db_file_path;
+use crate::vectordbapi::{configured_table_name, embeddings_dir, EmbeddingsStore};
+use rusqlite::{Connection, OpenFlags};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// Health report for the whole stack, as returned by [`diagnostics`].
+///
+/// Each check records its outcome independently, so one failing component does not hide
+/// the state of the others. Failure details are collected in `errors`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnostics {
+    /// Path of the config file that was checked, or the config directory if the profile
+    /// name is invalid.
+    pub config_path: PathBuf,
+    /// Whether the config file was read and parsed successfully.
+    pub config_ok: bool,
+    /// The configured vault directory, if any.
+    pub vault_dir: Option<PathBuf>,
+    /// Whether the configured vault directory exists.
+    pub vault_dir_exists: bool,
+    /// Path of the SQLite pagetable database.
+    pub db_path: PathBuf,
+    /// Whether the database file exists.
+    pub db_present: bool,
+    /// Whether the database could be opened and its records counted.
+    pub db_ok: bool,
+    /// Number of rows in the pagetable, if the database is reachable.
+    pub db_row_count: Option<usize>,
+    /// Directory the sentence-transformer model is loaded from.
+    pub model_dir: PathBuf,
+    /// Whether the model directory contains every required model file (see
+    /// `sentence_transformer::is_model_installed`).
+    pub model_present: bool,
+    /// Path of the nlprule tokenizer binary.
+    pub tokenizer_path: PathBuf,
+    /// Whether the tokenizer binary exists.
+    pub tokenizer_present: bool,
+    /// LanceDB directory of the embeddings store.
+    pub embeddings_dir: PathBuf,
+    /// Whether the embeddings directory exists.
+    pub embeddings_present: bool,
+    /// Whether the embeddings store could be opened.
+    pub embeddings_ok: bool,
+    /// Number of stored embeddings, if the store is reachable.
+    pub embeddings_row_count: Option<usize>,
+    /// Whether an ANN index exists on the embeddings table.
+    pub embeddings_index_present: bool,
+    /// Human-readable descriptions of every failed check.
+    pub errors: Vec<String>,
+}
+
+/// Checks every component of the stack and reports their state in one call.
+///
+/// This never fails; problems are reported in the returned [`Diagnostics`]. Nothing is
+/// created or migrated: a missing database or embeddings directory is reported as absent
+/// and an existing database is only read, so checking a fresh or broken install doesn't
+/// change what is being checked.
+pub async fn diagnostics() -> Diagnostics {
+    let mut errors = Vec::new();
+
+    // Config and vault directory.
+    let config = get_config_file_path().map(|path| {
+        let config = get_config_from(&path);
+        (path, config)
+    });
+    let (config_path, config) = match config {
+        Ok((path, config)) => (path, config),
+        Err(e) => (get_config_dir(), Err(e)),
+    };
+    let (config_ok, vault_dir) = match config {
+        Ok(config) => (true, config.vault_dir),
+        Err(e) => {
+            errors.push(format!("Config: {}", e));
+            (false, None)
+        }
+    };
+    let vault_dir_exists = vault_dir.as_deref().is_some_and(Path::is_dir);
+    if let (Some(dir), false) = (&vault_dir, vault_dir_exists) {
+        errors.push(format!("Vault directory does not exist: {}", dir.display()));
+    }
+
+    // SQLite pagetable.
+    let db_path = get_db_file_path();
+    let db_present = db_path.is_file();
+    let db_row_count = check_database(&db_path, &mut errors);
+
+    // Model and tokenizer files.
+    let model_dir = get_model_dir();
+    let model_present = is_model_installed(&model_dir);
+    if !model_present {
+        errors.push(format!("Model not found at {}", model_dir.display()));
+    }
+    let tokenizer_path = get_tokenizer_path();
+    let tokenizer_present = tokenizer_path.is_file();
+    if !tokenizer_present {
+        errors.push(format!(
+            "Tokenizer not found at {}",
+            tokenizer_path.display()
+        ));
+    }
+
+    // Embeddings store.
+    let embeddings_dir = embeddings_dir();
+    let embeddings_present = embeddings_dir.is_dir();
+    let embeddings = check_embeddings(&embeddings_dir, &db_path, &mut errors).await;
+
+    Diagnostics {
+        config_path,
+        config_ok,
+        vault_dir,
+        vault_dir_exists,
+        db_path,
+        db_present,
+        db_ok: db_row_count.is_some(),
+        db_row_count,
+        model_dir,
+        model_present,
+        tokenizer_path,
+        tokenizer_present,
+        embeddings_dir,
+        embeddings_present,
+        embeddings_ok: embeddings.is_some(),
+        embeddings_row_count: embeddings.and_then(|(count, _)| count),
+        embeddings_index_present: embeddings.is_some_and(|(_, index)| index),
+        errors,
+    }
+}
+
+/// Counts the pagetable rows of the database at `db_path` without creating or migrating it.
+fn check_database(db_path: &Path, errors: &mut Vec<String>) -> Option<usize> {
+    if !db_path.is_file() {
+        errors.push(format!("Database not found at {}", db_path.display()));
+        return None;
+    }
+    let count =
+        Connection::open_with_flags(db_path, OpenFlags::SQLITE_OPEN_READ_ONLY).and_then(|conn| {
+            conn.query_row("SELECT COUNT(*) FROM pagetable", [], |row| {
+                row.get::<_, i64>(0)
+            })
+        });
+    match count {
+        Ok(count) => Some(count as usize),
+        Err(e) => {
+            errors.push(format!("Database: {}", e));
+            None
+        }
+    }
+}
+
+/// Opens the embeddings store in `embeddings_dir` if the directory exists, and returns its
+/// row count and whether it has a vector index. `None` means the store couldn't be opened.
+async fn check_embeddings(
+    embeddings_dir: &Path,
+    db_path: &Path,
+    errors: &mut Vec<String>,
+) -> Option<(Option<usize>, bool)> {
+    if !embeddings_dir.is_dir() {
+        errors.push(format!(
+            "Embeddings directory not found at {}",
+            embeddings_dir.display()
+        ));
+        return None;
+    }
+    let store =
+        match EmbeddingsStore::open_at(embeddings_dir, db_path, &configured_table_name()).await {
+            Ok(store) => store,
+            Err(e) => {
+                errors.push(format!("Embeddings store: {}", e));
+                return None;
+            }
+        };
+    let row_count = match store.count_rows().await {
+        Ok(count) => Some(count),
+        Err(e) => {
+            errors.push(format!("Embeddings row count: {}", e));
+            None
+        }
+    };
+    let index_present = match store.has_vector_index().await {
+        Ok(present) => present,
+        Err(e) => {
+            errors.push(format!("Embeddings index: {}", e));
+            false
+        }
+    };
+    Some((row_count, index_present))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_missing_paths_are_not_created() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let db_path = dir.path().join("database").join("pagetable.sqlite");
+        let embeddings_dir = dir.path().join("embeddings");
+        let mut errors = Vec::new();
+
+        assert_eq!(check_database(&db_path, &mut errors), None);
+        assert!(check_embeddings(&embeddings_dir, &db_path, &mut errors)
+            .await
+            .is_none());
+        assert_eq!(errors.len(), 2);
+        assert!(!db_path.parent().unwrap().exists());
+        assert!(!embeddings_dir.exists());
+    }
+
+    #[test]
+    fn test_check_database_leaves_database_unchanged() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let db_path = dir.path().join("pagetable.sqlite");
+        // An old schema, which a migration would extend.
+        let conn = Connection::open(&db_path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE pagetable (id INTEGER PRIMARY KEY, lpath TEXT, title TEXT);
+             INSERT INTO pagetable (lpath, title) VALUES ('/a.md', 'A'), ('/b.md', 'B');",
+        )
+        .unwrap();
+        drop(conn);
+        let before = std::fs::read(&db_path).unwrap();
+
+        let mut errors = Vec::new();
+        assert_eq!(check_database(&db_path, &mut errors), Some(2));
+        assert!(errors.is_empty());
+        assert_eq!(std::fs::read(&db_path).unwrap(), before);
+    }
+}
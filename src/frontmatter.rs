@@ -0,0 +1,77 @@
+use serde_yaml::Value;
+
+/// Splits a note into its parsed YAML frontmatter and its body.
+///
+/// See [`split_raw`] for what counts as frontmatter. The frontmatter is `None` if the note
+/// has none, if it is empty, or if it is not valid YAML; the body excludes the frontmatter
+/// block in every case where [`split_raw`] finds one.
+pub fn split(content: &str) -> (Option<Value>, String) {
+    let (raw, body) = split_raw(content);
+    let frontmatter = raw
+        .and_then(|raw| serde_yaml::from_str::<Value>(raw).ok())
+        .filter(|value| !value.is_null());
+    (frontmatter, body.to_string())
+}
+
+/// Splits a note into its raw frontmatter text and its body, without parsing the YAML.
+///
+/// Frontmatter must start on the first non-blank line with a line that is exactly `---`,
+/// and ends at the next line that is exactly `---` (or `...`). Only that first block is
+/// taken, so `---` lines further down the body (e.g. horizontal rules) are left alone. If
+/// the opening delimiter is never closed, the note has no frontmatter and the whole
+/// content is the body. Blank lines between the closing delimiter and the body are dropped.
+pub fn split_raw(content: &str) -> (Option<&str>, &str) {
+    let trimmed = content.trim_start();
+    let (first_line, rest) = match trimmed.find('\n') {
+        Some(i) => trimmed.split_at(i + 1),
+        None => (trimmed, ""),
+    };
+    if first_line.trim_end() != "---" {
+        return (None, content);
+    }
+
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        let delimiter = line.trim_end();
+        if delimiter == "---" || delimiter == "..." {
+            let body = rest[offset + line.len()..].trim_start_matches(['\r', '\n']);
+            return (Some(&rest[..offset]), body);
+        }
+        offset += line.len();
+    }
+    (None, content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_frontmatter_and_body() {
+        let (frontmatter, body) = split("---\ntitle: Note\ntags: [a, b]\n---\n\nBody text.\n");
+        let frontmatter = frontmatter.unwrap();
+        assert_eq!(frontmatter["title"].as_str(), Some("Note"));
+        assert_eq!(body, "Body text.\n");
+    }
+
+    #[test]
+    fn test_split_keeps_rules_in_body() {
+        let content = "---\ntitle: Note\n---\nAbove\n---\nBelow\n";
+        let (raw, body) = split_raw(content);
+        assert_eq!(raw, Some("title: Note\n"));
+        assert_eq!(body, "Above\n---\nBelow\n");
+
+        // A note without frontmatter may still contain rules.
+        let content = "Intro\n---\nMore\n";
+        assert_eq!(split_raw(content), (None, content));
+    }
+
+    #[test]
+    fn test_split_unclosed_or_missing_frontmatter() {
+        let content = "---\ntitle: Never closed\nBody\n";
+        assert_eq!(split_raw(content), (None, content));
+        assert_eq!(split("Just a body").0, None);
+        assert_eq!(split("---\n---\nBody").1, "Body");
+        assert_eq!(split("---\r\ntitle: Crlf\r\n---\r\nBody").1, "Body");
+    }
+}
@@ -1,5 +1,12 @@
 pub mod ai;
 pub mod confapi;
 pub mod dbapi;
+pub mod diagnostics;
+pub mod frontmatter;
+pub mod scanner;
 pub mod utils;
 pub mod vectordbapi;
+pub mod vectorstore;
+
+pub use diagnostics::{diagnostics, Diagnostics};
+pub use vectordbapi::semantic_search;
@@ -0,0 +1,125 @@
+use crate::utils::{hash_content, normalize_path};
+use rayon::prelude::*;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// A markdown file found by [`Scanner::scan`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScannedFile {
+    /// Normalized path of the file (see `utils::normalize_path`).
+    pub lpath: String,
+    /// Hash of the file contents (see `utils::hash_content`).
+    pub content_hash: String,
+}
+
+/// Finds the markdown files under a directory and hashes them in parallel.
+///
+/// Hashing runs on a dedicated rayon thread pool, so a scan doesn't compete with other
+/// users of the global pool. By default the pool has rayon's automatic number of threads;
+/// use [`with_threads`](Self::with_threads) to cap it.
+#[derive(Debug, Clone)]
+pub struct Scanner {
+    root: PathBuf,
+    threads: usize,
+}
+
+impl Scanner {
+    /// Creates a scanner for the directory `root`.
+    pub fn new(root: &Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            threads: 0,
+        }
+    }
+
+    /// Hashes files on `threads` threads. 0 keeps rayon's automatic choice.
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    /// Returns every `.md` file under the root, sorted by lpath.
+    ///
+    /// Hidden files and directories (names starting with `.`) are skipped, and symlinked
+    /// directories are not followed.
+    pub fn scan(&self) -> io::Result<Vec<ScannedFile>> {
+        let mut paths = Vec::new();
+        collect_markdown_files(&self.root, &mut paths)?;
+
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(self.threads)
+            .build()
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        let mut files = pool.install(|| {
+            paths
+                .par_iter()
+                .map(|path| {
+                    Ok(ScannedFile {
+                        lpath: normalize_path(&path.to_string_lossy()),
+                        content_hash: hash_content(&fs::read(path)?),
+                    })
+                })
+                .collect::<io::Result<Vec<_>>>()
+        })?;
+        files.sort_by(|a, b| a.lpath.cmp(&b.lpath));
+        Ok(files)
+    }
+}
+
+/// Scans `root` with the default thread count. See [`Scanner::scan`].
+pub fn scan_markdown_files(root: &Path) -> io::Result<Vec<ScannedFile>> {
+    Scanner::new(root).scan()
+}
+
+/// Adds the paths of the markdown files under `dir` to `paths`, recursing into
+/// subdirectories.
+fn collect_markdown_files(dir: &Path, paths: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        let path = entry.path();
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            collect_markdown_files(&path, paths)?;
+        } else if path.extension().is_some_and(|ext| ext == "md") {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_with_thread_count() {
+        let dir = tempfile::TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("sub")).unwrap();
+        fs::create_dir_all(dir.path().join(".git")).unwrap();
+        fs::write(dir.path().join("a.md"), "first").unwrap();
+        fs::write(dir.path().join("sub").join("b.md"), "second").unwrap();
+        fs::write(dir.path().join("notes.txt"), "not markdown").unwrap();
+        fs::write(dir.path().join(".git").join("c.md"), "hidden").unwrap();
+
+        let files = Scanner::new(dir.path()).with_threads(2).scan().unwrap();
+        let expected: Vec<ScannedFile> = [("a.md", "first"), ("sub/b.md", "second")]
+            .iter()
+            .map(|(name, content)| ScannedFile {
+                lpath: normalize_path(&dir.path().join(name).to_string_lossy()),
+                content_hash: hash_content(content.as_bytes()),
+            })
+            .collect();
+        assert_eq!(files, expected);
+
+        // The thread count doesn't change the result.
+        assert_eq!(scan_markdown_files(dir.path()).unwrap(), expected);
+        assert_eq!(
+            Scanner::new(dir.path()).with_threads(1).scan().unwrap(),
+            expected
+        );
+    }
+}
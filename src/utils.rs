@@ -1,11 +1,65 @@
-use crate::dbapi::{self, delete_record, get_db_file_path, run_migrations, RecordIdentifier};
+use crate::confapi;
+use crate::dbapi::{self, get_db_file_path, run_migrations, RecordIdentifier};
+use crate::frontmatter;
+use log::warn;
 use rusqlite::{Connection, OptionalExtension};
 use std::collections::HashMap;
 use std::error::Error;
 use std::fs;
-use std::path::Path;
+use std::path::{Component, Path, PathBuf};
+
+/// Normalizes a physical path so different spellings of the same file compare equal.
+///
+/// If the path exists it is canonicalized (made absolute with symlinks resolved).
+/// Otherwise it is cleaned lexically: `.` components are dropped and `..` removes the
+/// preceding component. Every lpath is normalized this way before it is stored in or
+/// compared against the pagetable and the embeddings store.
+pub fn normalize_path(path: &str) -> String {
+    if let Ok(canonical) = fs::canonicalize(path) {
+        return canonical.to_string_lossy().into_owned();
+    }
+    let mut cleaned = PathBuf::new();
+    for component in Path::new(path).components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match cleaned.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    cleaned.pop();
+                }
+                _ => cleaned.push(".."),
+            },
+            other => cleaned.push(other.as_os_str()),
+        }
+    }
+    cleaned.to_string_lossy().into_owned()
+}
+
+/// Computes the content hash stored in the pagetable's `content_hash` column.
+///
+/// This is the hex-encoded BLAKE3 hash of the bytes.
+pub fn hash_content(bytes: &[u8]) -> String {
+    blake3::hash(bytes).to_hex().to_string()
+}
+
+/// Reads the file at `path` and returns its content hash (see [`hash_content`]).
+pub fn hash_file(path: &str) -> std::io::Result<String> {
+    Ok(hash_content(&fs::read(path)?))
+}
+
+/// Number of leading bytes inspected by [`looks_binary`].
+const BINARY_SNIFF_LEN: usize = 8 * 1024;
+
+/// Returns whether `bytes` look like a binary file rather than a text note.
+///
+/// A file is treated as binary if its first 8 KB contain a NUL byte, or if it is a
+/// git-LFS pointer (whose real content lives elsewhere).
+pub fn looks_binary(bytes: &[u8]) -> bool {
+    let head = &bytes[..bytes.len().min(BINARY_SNIFF_LEN)];
+    head.contains(&0) || head.starts_with(b"version https://git-lfs.github.com/spec/")
+}
 
 /// Returns an array of strings containing the lpaths and/or vpaths from all records in the pagetable.
+/// Trashed records are left out.
 /// If both booleans are true, both fields will be included (in order per record).
 pub fn get_all_paths(
     include_lpath: bool,
@@ -24,7 +78,10 @@ pub fn get_all_paths(
     if fields.is_empty() {
         return Ok(Vec::new());
     }
-    let query = format!("SELECT {} FROM pagetable", fields.join(", "));
+    let query = format!(
+        "SELECT {} FROM pagetable WHERE deleted_at IS NULL",
+        fields.join(", ")
+    );
     let mut stmt = conn.prepare(&query)?;
     let mut rows = stmt.query([])?;
     let mut results = Vec::new();
@@ -45,24 +102,130 @@ pub fn get_all_paths(
     Ok(results)
 }
 
-/// Iterates through all lpaths in the database and deletes the record if the file does not exist on disk.
-pub fn cleanup_stale_records() -> Result<(), dbapi::DbError> {
+/// Number of days a record stays in the trash before [`cleanup_stale_records`] purges it.
+pub const STALE_GRACE_PERIOD_DAYS: i64 = 7;
+
+/// Outcome of [`cleanup_stale_records`]; each list holds lpaths.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CleanupReport {
+    /// Records whose file went missing and were moved to the trash.
+    pub trashed: Vec<String>,
+    /// Trashed records whose file is back and were restored.
+    pub restored: Vec<String>,
+    /// Records missing for longer than the grace period, deleted for good.
+    pub purged: Vec<String>,
+    /// Records left alone because their vault root is inaccessible.
+    pub skipped: Vec<String>,
+}
+
+/// Iterates through all lpaths in the database and cleans up records whose file does not
+/// exist on disk, using a grace period of [`STALE_GRACE_PERIOD_DAYS`].
+///
+/// See [`cleanup_stale_records_with_grace`].
+pub fn cleanup_stale_records(dry_run: bool) -> Result<CleanupReport, dbapi::DbError> {
+    cleanup_stale_records_with_grace(dry_run, chrono::Duration::days(STALE_GRACE_PERIOD_DAYS))
+}
+
+/// Iterates through all lpaths in the database and cleans up records whose file does not
+/// exist on disk.
+///
+/// Cleanup is mark-then-sweep, so a briefly unavailable file doesn't lose its record:
+/// - a record whose file is missing is moved to the trash (see `dbapi::soft_delete_record`);
+/// - a trashed record whose file is back is restored;
+/// - a trashed record whose file is still missing after `grace` is deleted for good.
+///
+/// If the configured `vault_dir` can't be read (e.g. a network drive is unmounted), every
+/// file under it looks missing, so records under it are skipped instead of trashed. If the
+/// configuration itself can't be read, the vault root is unknown, so every record whose
+/// file is missing is skipped.
+///
+/// With `dry_run` set nothing is changed, so the report is what a real run would do.
+pub fn cleanup_stale_records_with_grace(
+    dry_run: bool,
+    grace: chrono::Duration,
+) -> Result<CleanupReport, dbapi::DbError> {
     run_migrations()?;
     let db_file_path = get_db_file_path();
-    let conn = Connection::open(db_file_path)?;
-    let mut stmt = conn.prepare("SELECT lpath FROM pagetable")?;
-    let lpath_iter = stmt.query_map([], |row| row.get::<_, String>(0))?;
-    let mut stale_paths = Vec::new();
-    for lpath_result in lpath_iter {
-        let lpath: String = lpath_result?;
-        if !Path::new(&lpath).exists() {
-            stale_paths.push(lpath);
+    let mut conn = Connection::open(db_file_path)?;
+    match inaccessible_vault_root() {
+        Ok(Some(root)) => {
+            cleanup_stale_records_in(&mut conn, dry_run, grace, |lpath| lpath.starts_with(&root))
+        }
+        Ok(None) => cleanup_stale_records_in(&mut conn, dry_run, grace, |_| false),
+        Err(e) => {
+            warn!(
+                "Could not read the configuration, skipping records with missing files: {}",
+                e
+            );
+            cleanup_stale_records_in(&mut conn, dry_run, grace, |_| true)
+        }
+    }
+}
+
+/// Runs [`cleanup_stale_records_with_grace`] on an open connection. Records whose file is
+/// missing are skipped when `skip_missing` returns true for their lpath.
+///
+/// All changes are made in one transaction, so a failure leaves the pagetable untouched.
+pub(crate) fn cleanup_stale_records_in(
+    conn: &mut Connection,
+    dry_run: bool,
+    grace: chrono::Duration,
+    skip_missing: impl Fn(&Path) -> bool,
+) -> Result<CleanupReport, dbapi::DbError> {
+    let rows: Vec<(String, Option<String>)> = conn
+        .prepare("SELECT lpath, deleted_at FROM pagetable")?
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<_, _>>()?;
+
+    let cutoff = chrono::Utc::now() - grace;
+    let mut report = CleanupReport::default();
+    for (lpath, deleted_at) in rows {
+        let exists = Path::new(&lpath).exists();
+        if !exists && skip_missing(Path::new(&lpath)) {
+            report.skipped.push(lpath);
+            continue;
+        }
+        match deleted_at {
+            None if !exists => report.trashed.push(lpath),
+            Some(_) if exists => report.restored.push(lpath),
+            Some(deleted_at) if !exists => {
+                // A timestamp that doesn't parse can't be past the grace period; keep the record.
+                let expired = chrono::DateTime::parse_from_rfc3339(&deleted_at)
+                    .map(|deleted_at| deleted_at < cutoff)
+                    .unwrap_or(false);
+                if expired {
+                    report.purged.push(lpath);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if !dry_run {
+        let tx = conn.transaction()?;
+        for l in &report.trashed {
+            dbapi::soft_delete_record_in(&tx, RecordIdentifier::Lpath(l.clone()))?;
         }
+        for l in &report.restored {
+            dbapi::restore_record_in(&tx, RecordIdentifier::Lpath(l.clone()))?;
+        }
+        dbapi::delete_lpaths_in(&tx, &report.purged)?;
+        tx.commit()?;
     }
-    for l in stale_paths {
-        delete_record(RecordIdentifier::Lpath(l))?;
+    Ok(report)
+}
+
+/// Returns the configured `vault_dir` (normalized) if it is set but can't be read.
+fn inaccessible_vault_root() -> Result<Option<PathBuf>, confapi::ConfigError> {
+    let Some(vault_dir) = confapi::get_config()?.vault_dir else {
+        return Ok(None);
+    };
+    if fs::read_dir(&vault_dir).is_ok() {
+        return Ok(None);
     }
-    Ok(())
+    Ok(Some(PathBuf::from(normalize_path(
+        &vault_dir.to_string_lossy(),
+    ))))
 }
 
 /// Given a vpath, returns the corresponding lpath from the database.
@@ -70,67 +233,135 @@ pub fn get_lpath(vpath: &str) -> Result<Option<String>, dbapi::DbError> {
     run_migrations()?;
     let db_file_path = get_db_file_path();
     let conn = Connection::open(db_file_path)?;
-    let mut stmt = conn.prepare("SELECT lpath FROM pagetable WHERE vpath = ?")?;
+    let mut stmt =
+        conn.prepare("SELECT lpath FROM pagetable WHERE vpath = ? AND deleted_at IS NULL")?;
     let result = stmt.query_row([vpath], |row| row.get(0)).optional()?;
     Ok(result)
 }
 
+/// Error type returned by [`read_file`].
+#[derive(Debug)]
+pub enum ReadError {
+    /// No record in the pagetable has the given vpath.
+    NotFound {
+        vpath: String,
+    },
+    /// Neither an lpath nor a vpath was provided.
+    MissingPath,
+    /// The file looks binary (see [`looks_binary`]) and was not read as text.
+    Binary {
+        path: String,
+    },
+    Io(std::io::Error),
+    Db(dbapi::DbError),
+}
+
+impl std::fmt::Display for ReadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReadError::NotFound { vpath } => {
+                write!(f, "No corresponding lpath found for vpath: {}", vpath)
+            }
+            ReadError::MissingPath => write!(f, "At least one of lpath or vpath must be provided"),
+            ReadError::Binary { path } => write!(f, "{} looks binary, skipped", path),
+            ReadError::Io(e) => write!(f, "IO Error: {}", e),
+            ReadError::Db(e) => write!(f, "Database Error: {}", e),
+        }
+    }
+}
+
+impl Error for ReadError {}
+
+impl From<std::io::Error> for ReadError {
+    fn from(err: std::io::Error) -> Self {
+        ReadError::Io(err)
+    }
+}
+
+impl From<dbapi::DbError> for ReadError {
+    fn from(err: dbapi::DbError) -> Self {
+        ReadError::Db(err)
+    }
+}
+
 /// Reads a file from disk.
 /// You must supply at least one of `lpath` or `vpath`. If only `vpath` is provided, the function
 /// will lookup the corresponding lpath from the database.
 /// The `metadata` flag (default true) indicates whether to keep YAML frontmatter.
-/// If false, the returned content is stripped of YAML frontmatter.
+/// If false, the returned content is stripped of YAML frontmatter (see `frontmatter::split_raw`).
+///
+/// An unknown `vpath` returns [`ReadError::NotFound`], so callers can map it to a 404.
+/// Files that look binary (see [`looks_binary`]) return [`ReadError::Binary`].
 pub fn read_file(
     lpath: Option<&str>,
     vpath: Option<&str>,
     metadata: bool,
-) -> Result<String, Box<dyn Error>> {
+) -> Result<String, ReadError> {
     let path_str = if let Some(l) = lpath {
         l.to_string()
     } else if let Some(v) = vpath {
         match get_lpath(v)? {
             Some(found) => found,
-            None => return Err("No corresponding lpath found for provided vpath".into()),
+            None => {
+                return Err(ReadError::NotFound {
+                    vpath: v.to_string(),
+                })
+            }
         }
     } else {
-        return Err("At least one of lpath or vpath must be provided".into());
+        return Err(ReadError::MissingPath);
     };
 
-    let content = fs::read_to_string(&path_str)?;
+    let bytes = fs::read(&path_str)?;
+    if looks_binary(&bytes) {
+        return Err(ReadError::Binary { path: path_str });
+    }
+    let content = String::from_utf8(bytes)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
     if metadata {
         Ok(content)
     } else {
-        // If content starts with YAML frontmatter delimited by '---'
-        if content.trim_start().starts_with("---") {
-            // Split into at most three parts: before frontmatter (often empty), frontmatter, and content.
-            let parts: Vec<&str> = content.splitn(3, "---").collect();
-            if parts.len() == 3 {
-                // Return the content after the frontmatter.
-                return Ok(parts[2].trim_start().to_string());
-            }
-        }
-        Ok(content)
+        Ok(frontmatter::split_raw(&content).1.to_string())
     }
 }
 
 /// Extracts and returns the YAML frontmatter (if any) from the file at the given lpath.
 pub fn get_metadata(lpath: &str) -> Result<Option<String>, Box<dyn Error>> {
     let content = fs::read_to_string(lpath)?;
-    if content.trim_start().starts_with("---") {
-        let parts: Vec<&str> = content.splitn(3, "---").collect();
-        if parts.len() >= 3 {
-            let metadata = parts[1].trim().to_string();
-            return Ok(Some(metadata));
-        }
-    }
-    Ok(None)
+    let (metadata, _body) = frontmatter::split_raw(&content);
+    Ok(metadata.map(|metadata| metadata.trim().to_string()))
 }
 
 pub fn get_records_by_column(
     columns: &[&str],
 ) -> Result<Vec<HashMap<String, Option<String>>>, Box<dyn Error>> {
+    let mut records = Vec::new();
+    for_each_record(columns, |record| {
+        records.push(record);
+        Ok(())
+    })?;
+    Ok(records)
+}
+
+/// Calls `f` with each pagetable record, selecting only the given columns.
+/// Trashed records are skipped.
+///
+/// Records are read one row at a time, so large tables are never loaded into memory at once.
+/// Returning an error from `f` stops the iteration and propagates the error.
+pub fn for_each_record<F>(columns: &[&str], mut f: F) -> Result<(), Box<dyn Error>>
+where
+    F: FnMut(HashMap<String, Option<String>>) -> Result<(), Box<dyn Error>>,
+{
     // List of allowed column names.
-    let allowed = ["id", "lpath", "title", "timestamp", "vpath", "project"];
+    let allowed = [
+        "id",
+        "lpath",
+        "title",
+        "timestamp",
+        "vpath",
+        "project",
+        "content_hash",
+    ];
     // Validate that each requested column is allowed.
     for &col in columns {
         if !allowed.contains(&col) {
@@ -138,9 +369,9 @@ pub fn get_records_by_column(
         }
     }
 
-    // If no columns are provided, return an empty vector.
+    // If no columns are provided, there is nothing to visit.
     if columns.is_empty() {
-        return Ok(Vec::new());
+        return Ok(());
     }
 
     // Ensure migrations have been run.
@@ -149,10 +380,12 @@ pub fn get_records_by_column(
     let conn = Connection::open(db_file_path)?;
 
     // Build the query using the specified columns.
-    let query = format!("SELECT {} FROM pagetable", columns.join(", "));
+    let query = format!(
+        "SELECT {} FROM pagetable WHERE deleted_at IS NULL",
+        columns.join(", ")
+    );
     let mut stmt = conn.prepare(&query)?;
     let mut rows = stmt.query([])?;
-    let mut records = Vec::new();
 
     while let Some(row) = rows.next()? {
         let mut record = HashMap::new();
@@ -167,9 +400,9 @@ pub fn get_records_by_column(
                 record.insert(col.to_string(), value);
             }
         }
-        records.push(record);
+        f(record)?;
     }
-    Ok(records)
+    Ok(())
 }
 
 #[cfg(test)]
@@ -198,6 +431,121 @@ This is the body of the document.
         assert!(!body.contains("title:"));
     }
 
+    #[test]
+    fn test_normalize_path() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let file = dir.path().join("note.md");
+        std::fs::write(&file, "body").unwrap();
+        let dotted = dir.path().join(".").join("note.md");
+        assert_eq!(
+            normalize_path(file.to_str().unwrap()),
+            normalize_path(dotted.to_str().unwrap())
+        );
+
+        // Paths that don't exist are cleaned lexically.
+        assert_eq!(
+            normalize_path("/no/such/./vault/../note.md"),
+            "/no/such/note.md"
+        );
+        assert_eq!(normalize_path("../a/./b.md"), "../a/b.md");
+    }
+
+    #[test]
+    fn test_hash_file_matches_hash_content() {
+        let mut file = NamedTempFile::new().unwrap();
+        write!(file, "same bytes").unwrap();
+        let file_path = file.path().to_str().unwrap();
+
+        assert_eq!(hash_file(file_path).unwrap(), hash_content(b"same bytes"));
+        assert_ne!(hash_content(b"same bytes"), hash_content(b"other bytes"));
+    }
+
+    #[test]
+    fn test_cleanup_stale_records_in() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let present = dir.path().join("present.md");
+        std::fs::write(&present, "body").unwrap();
+        let present = present.to_string_lossy().into_owned();
+        let missing = dir.path().join("missing.md").to_string_lossy().into_owned();
+        let expired = dir.path().join("expired.md").to_string_lossy().into_owned();
+        let unmounted = dir.path().join("unmounted");
+        let offline = unmounted.join("offline.md").to_string_lossy().into_owned();
+
+        let mut conn = Connection::open_in_memory().unwrap();
+        dbapi::migrate(&conn).unwrap();
+        let long_ago = "2000-01-01T00:00:00Z";
+        for (lpath, deleted_at) in [
+            (&present, Some(long_ago)),
+            (&missing, None),
+            (&expired, Some(long_ago)),
+            (&offline, None),
+        ] {
+            conn.execute(
+                "INSERT INTO pagetable (lpath, title, timestamp, vpath, deleted_at)
+                 VALUES (?1, 't', 'ts', '', ?2)",
+                rusqlite::params![lpath, deleted_at],
+            )
+            .unwrap();
+        }
+        let deleted_at = |conn: &Connection, lpath: &str| -> Option<Option<String>> {
+            conn.query_row(
+                "SELECT deleted_at FROM pagetable WHERE lpath = ?1",
+                [lpath],
+                |row| row.get(0),
+            )
+            .optional()
+            .unwrap()
+        };
+
+        let grace = chrono::Duration::days(STALE_GRACE_PERIOD_DAYS);
+        let report = cleanup_stale_records_in(&mut conn, false, grace, |lpath| {
+            lpath.starts_with(&unmounted)
+        })
+        .unwrap();
+        assert_eq!(
+            report,
+            CleanupReport {
+                trashed: vec![missing.clone()],
+                restored: vec![present.clone()],
+                purged: vec![expired.clone()],
+                skipped: vec![offline.clone()],
+            }
+        );
+        assert_eq!(deleted_at(&conn, &present), Some(None));
+        assert!(matches!(deleted_at(&conn, &missing), Some(Some(_))));
+        assert_eq!(deleted_at(&conn, &expired), None);
+        assert_eq!(deleted_at(&conn, &offline), Some(None));
+
+        // Without a known vault root (unreadable config) nothing missing is touched, even
+        // once the grace period is over.
+        let expired_grace = chrono::Duration::seconds(-1);
+        let report = cleanup_stale_records_in(&mut conn, false, expired_grace, |_| true).unwrap();
+        assert_eq!(report.skipped, vec![missing.clone(), offline.clone()]);
+        assert!(report.trashed.is_empty() && report.purged.is_empty());
+        assert!(matches!(deleted_at(&conn, &missing), Some(Some(_))));
+        assert_eq!(deleted_at(&conn, &offline), Some(None));
+    }
+
+    #[test]
+    fn test_read_file_without_paths() {
+        let result = read_file(None, None, true);
+        assert!(matches!(result, Err(ReadError::MissingPath)));
+    }
+
+    #[test]
+    fn test_read_file_skips_binary() {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(b"PK\x03\x04\x00\x00binary").unwrap();
+        let file_path = file.path().to_str().unwrap();
+
+        let result = read_file(Some(file_path), None, true);
+        assert!(matches!(result, Err(ReadError::Binary { .. })));
+        assert!(looks_binary(
+            b"version https://git-lfs.github.com/spec/v1\noid sha256:abc\n"
+        ));
+        assert!(!looks_binary(b"# A plain note\n"));
+    }
+
     #[test]
     fn test_get_metadata() {
         let mut file = NamedTempFile::new().unwrap();
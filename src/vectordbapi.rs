@@ -1,12 +1,19 @@
 use crate::dbapi;
+use crate::utils::{self, normalize_path};
 use arrow_array::types::Float32Type;
 
-use arrow_array::{ArrayRef, FixedSizeListArray, RecordBatch, RecordBatchIterator, StringArray};
+use arrow_array::{
+    ArrayRef, FixedSizeListArray, Float32Array, RecordBatch, RecordBatchIterator, StringArray,
+};
 use arrow_schema::{DataType, Field, Schema, SchemaRef};
 use chrono::Utc; // <-- Add this at the top of your file.
 use futures::TryStreamExt;
+use log::{debug, error, warn};
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use uuid::Uuid;
 
 use lancedb::index::scalar::FullTextSearchQuery;
 use lancedb::{
@@ -14,13 +21,19 @@ use lancedb::{
     index::scalar::FtsIndexBuilder,
     index::vector::IvfPqIndexBuilder,
     index::Index,
-    query::{ExecutableQuery, QueryBase},
+    query::{ExecutableQuery, QueryBase, Select},
     Connection, DistanceType, Error, Result, Table,
 };
 
+use crate::ai::embedder::{embedder_from_config, Embedder, LocalEmbedder};
+use crate::ai::sentence_transformer::{self, MODEL_NAME};
 use crate::confapi;
+use crate::vectorstore::VectorStore;
+use async_trait::async_trait;
 
+/// Default embedding dimension: the native output size of [`MODEL_NAME`].
 const EMBEDDING_DIM: usize = 384;
+/// Base name of the embeddings table; a configured prefix is prepended to it.
 const TABLE_NAME: &str = "embeddings";
 
 /// Metadata associated with an embedding.
@@ -45,19 +58,194 @@ pub struct DocumentEmbedding {
     pub content: String,
 }
 
+/// Number of rows removed by [`EmbeddingsStore::reset_project`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ResetCounts {
+    /// Pagetable records deleted.
+    pub records: usize,
+    /// Embeddings deleted.
+    pub embeddings: usize,
+}
+
+/// A single vector search result, as returned by [`EmbeddingsStore::search_hits`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchHit {
+    /// Full text content of the matched record.
+    pub content: String,
+    /// Metadata of the matched record.
+    pub metadata: EmbeddingMetadata,
+    /// Similarity to the query in `[0, 1]`; higher is more similar, `1.0` is an exact match.
+    pub score: f32,
+}
+
+/// A column of the embeddings table that can be used in a [`MetadataFilter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterColumn {
+    Id,
+    Title,
+    Path,
+}
+
+impl FilterColumn {
+    fn as_str(&self) -> &'static str {
+        match self {
+            FilterColumn::Id => "id",
+            FilterColumn::Title => "title",
+            FilterColumn::Path => "path",
+        }
+    }
+}
+
+/// A typed filter over embedding metadata that is safe to build from user input.
+///
+/// Column names come from [`FilterColumn`], so they can't be injected. Values are
+/// emitted as SQL string literals: the value is wrapped in single quotes and every
+/// single quote inside it is doubled (`john's` becomes `'john''s'`).
+#[derive(Debug, Clone)]
+pub struct MetadataFilter {
+    predicate: String,
+}
+
+impl MetadataFilter {
+    /// Matches rows where `column` equals `value`.
+    pub fn eq(column: FilterColumn, value: &str) -> Self {
+        Self {
+            predicate: format!("{} = '{}'", column.as_str(), escape_sql_literal(value)),
+        }
+    }
+
+    /// Matches rows that satisfy both this filter and `other`.
+    pub fn and(self, other: MetadataFilter) -> Self {
+        Self {
+            predicate: format!("({}) AND ({})", self.predicate, other.predicate),
+        }
+    }
+
+    /// Returns the predicate string passed to LanceDB.
+    pub fn as_predicate(&self) -> &str {
+        &self.predicate
+    }
+}
+
+/// Escapes a value for use inside a single-quoted LanceDB SQL string literal by doubling
+/// every embedded single quote (`john's` becomes `john''s`).
+///
+/// Every predicate built from a path, title or other caller-supplied value must go through
+/// this; the caller still supplies the surrounding quotes.
+pub fn escape_sql_literal(value: &str) -> String {
+    value.replace('\'', "''")
+}
+
+/// Builds a predicate matching `path` in both normalized and raw form, for rows stored
+/// before paths were normalized.
+fn path_predicate(path: &str) -> String {
+    paths_predicate(&[path])
+}
+
+/// Like [`path_predicate`], but matches any of `paths`.
+fn paths_predicate<S: AsRef<str>>(paths: &[S]) -> String {
+    let literals: Vec<String> = paths
+        .iter()
+        .flat_map(|path| {
+            let path = path.as_ref();
+            [normalize_path(path), path.to_string()]
+        })
+        .map(|path| format!("'{}'", escape_sql_literal(&path)))
+        .collect();
+    format!("path IN ({})", literals.join(", "))
+}
+
+/// Schema metadata key recording the embedding dimension of a table.
+const DIMENSION_METADATA_KEY: &str = "notemancy.embedding_dim";
+/// Schema metadata key recording the distance metric of a table.
+const DISTANCE_METADATA_KEY: &str = "notemancy.distance_type";
+/// Schema metadata key recording the name of the model that produced a table's embeddings.
+const MODEL_METADATA_KEY: &str = "notemancy.model";
+
+/// Distance metrics that can be recorded in table metadata, with their stored names.
+const DISTANCE_NAMES: [(DistanceType, &str); 3] = [
+    (DistanceType::Cosine, "cosine"),
+    (DistanceType::L2, "l2"),
+    (DistanceType::Dot, "dot"),
+];
+
+fn distance_name(distance: DistanceType) -> &'static str {
+    DISTANCE_NAMES
+        .iter()
+        .find(|(d, _)| *d == distance)
+        .map(|(_, name)| *name)
+        .unwrap_or("cosine")
+}
+
+fn distance_from_name(name: &str) -> Option<DistanceType> {
+    DISTANCE_NAMES
+        .iter()
+        .find(|(_, n)| *n == name)
+        .map(|(d, _)| *d)
+}
+
+/// Describes how the embeddings in a table were produced, as returned by
+/// [`EmbeddingsStore::model_info`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelInfo {
+    /// Name of the model, or `None` for tables created before the model was recorded.
+    pub model: Option<String>,
+    /// Vector dimension of the table.
+    pub dimension: usize,
+    /// Distance metric of the table.
+    pub distance: DistanceType,
+}
+
 /// Manager for storing and retrieving embeddings.
+///
+/// `EmbeddingsStore` is `Send + Sync` and cheap to clone: the LanceDB connection and table
+/// handles are internally reference counted, so clones share one connection instead of
+/// reopening it. Every read or write method takes `&self`, so one store (or its clones)
+/// can serve many async tasks or server handlers. Only [`create_table`](Self::create_table)
+/// and [`clear`](Self::clear) need `&mut self`, and they only update the store they are
+/// called on; put the store behind an `Arc<tokio::sync::RwLock<_>>` if they must run while
+/// it is shared.
+#[derive(Clone)]
 pub struct EmbeddingsStore {
     connection: Connection,
+    /// Name of the LanceDB table this store reads and writes.
+    table_name: String,
     table: Option<Table>,
+    /// Vector dimension of the table; every stored and queried vector must match it.
+    dimension: usize,
+    /// Distance metric the table was created for.
+    distance: DistanceType,
+    /// Model recorded in the table metadata, if any.
+    model: Option<String>,
+    /// SQLite database holding the pagetable records written alongside embeddings.
+    db_path: PathBuf,
 }
 
 impl EmbeddingsStore {
     /// Create a new embeddings store.
     ///
     /// This function uses the new confapi module to determine the database directory.
+    /// The table name is taken from the configuration (see [`configured_table_name`]), and
+    /// pagetable records go to the config directory database (see `dbapi::get_db_file_path`).
     pub async fn new() -> Result<Self> {
-        let config_dir = confapi::get_config_dir();
-        let embeddings_dir = config_dir.join("embeddings");
+        Self::with_table_name(&configured_table_name()).await
+    }
+
+    /// Create a new embeddings store that uses the given table name.
+    ///
+    /// Several stores with different table names can share one LanceDB directory,
+    /// e.g. to keep separate embedding spaces per vault or tenant.
+    pub async fn with_table_name(table_name: &str) -> Result<Self> {
+        Self::open_at(&embeddings_dir(), &dbapi::get_db_file_path(), table_name).await
+    }
+
+    /// Create an embeddings store in the LanceDB directory `embeddings_dir`, using the
+    /// given table name and writing pagetable records to the SQLite database at `db_path`.
+    ///
+    /// Nothing is read from the configuration or written to the config directory, so
+    /// embedding applications can choose every location themselves. The directory is
+    /// created if needed, and the database on first use.
+    pub async fn open_at(embeddings_dir: &Path, db_path: &Path, table_name: &str) -> Result<Self> {
         if !embeddings_dir.exists() {
             std::fs::create_dir_all(&embeddings_dir).map_err(|e| Error::Other {
                 message: format!("Failed to create embeddings directory: {}", e),
@@ -65,45 +253,218 @@ impl EmbeddingsStore {
             })?;
         }
         let connection = connect(&embeddings_dir.to_string_lossy()).execute().await?;
+        Self::from_connection(connection, db_path, table_name).await
+    }
+
+    /// Create an embeddings store on an already open LanceDB connection, writing pagetable
+    /// records to the SQLite database at `db_path`.
+    ///
+    /// Use this to share one connection between stores with different table names
+    /// instead of connecting once per store.
+    pub async fn from_connection(
+        connection: Connection,
+        db_path: &Path,
+        table_name: &str,
+    ) -> Result<Self> {
         let mut store = Self {
             connection,
+            table_name: table_name.to_string(),
             table: None,
+            dimension: EMBEDDING_DIM,
+            distance: DistanceType::Cosine,
+            model: None,
+            db_path: db_path.to_path_buf(),
         };
 
-        let tables = store.connection.table_names().execute().await?;
-        if tables.contains(&TABLE_NAME.to_string()) {
-            store.table = Some(store.connection.open_table(TABLE_NAME).execute().await?);
+        if store.table_exists().await? {
+            store.open_existing_table().await?;
         }
         Ok(store)
     }
 
+    /// Opens the existing table and loads its dimension and distance metric.
+    ///
+    /// The dimension is taken from the vector column itself. If the table also recorded a
+    /// dimension in its metadata and the two disagree, the table is rejected.
+    async fn open_existing_table(&mut self) -> Result<()> {
+        let table = self
+            .connection
+            .open_table(&self.table_name)
+            .execute()
+            .await?;
+        let schema = table.schema().await?;
+
+        let dimension = match schema.field_with_name("vector").map(|f| f.data_type()) {
+            Ok(DataType::FixedSizeList(_, size)) => *size as usize,
+            _ => {
+                return Err(Error::Other {
+                    message: format!(
+                        "Table '{}' has no fixed-size vector column",
+                        self.table_name
+                    ),
+                    source: None,
+                })
+            }
+        };
+        if let Some(recorded) = schema.metadata().get(DIMENSION_METADATA_KEY) {
+            if recorded.parse::<usize>().ok() != Some(dimension) {
+                return Err(Error::Other {
+                    message: format!(
+                        "Table '{}' records dimension {} but its vector column has {}",
+                        self.table_name, recorded, dimension
+                    ),
+                    source: None,
+                });
+            }
+        }
+
+        self.distance = schema
+            .metadata()
+            .get(DISTANCE_METADATA_KEY)
+            .and_then(|name| distance_from_name(name))
+            .unwrap_or(DistanceType::Cosine);
+        self.model = schema.metadata().get(MODEL_METADATA_KEY).cloned();
+        if let Some(model) = self.model.as_deref().filter(|m| *m != MODEL_NAME) {
+            warn!(
+                "Table '{}' was built with model '{}' but the configured model is '{}'; \
+                 similarity results will be meaningless until embeddings are rebuilt",
+                self.table_name, model, MODEL_NAME
+            );
+        }
+        self.dimension = dimension;
+        self.table = Some(table);
+        Ok(())
+    }
+
+    /// Returns the model, dimension and distance metric recorded for the table.
+    pub fn model_info(&self) -> ModelInfo {
+        ModelInfo {
+            model: self.model.clone(),
+            dimension: self.dimension,
+            distance: self.distance,
+        }
+    }
+
+    /// Returns the path of the SQLite database the store writes pagetable records to.
+    pub fn db_path(&self) -> &Path {
+        &self.db_path
+    }
+
+    /// Opens the store's SQLite database (see `dbapi::open_db_at`).
+    fn db(&self) -> Result<rusqlite::Connection> {
+        dbapi::open_db_at(&self.db_path).map_err(sqlite_error)
+    }
+
+    /// Returns the vector dimension of the table.
+    pub fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    /// Returns the distance metric of the table.
+    pub fn distance_type(&self) -> DistanceType {
+        self.distance
+    }
+
+    /// Returns an error if `vector` does not have the table's dimension.
+    fn check_dimension(&self, vector: &[f32], what: &str) -> Result<()> {
+        if vector.len() != self.dimension {
+            return Err(Error::InvalidInput {
+                message: format!(
+                    "{} dimension {} does not match expected {}",
+                    what,
+                    vector.len(),
+                    self.dimension
+                ),
+            });
+        }
+        Ok(())
+    }
+
+    /// Returns the opened table, or `Error::TableNotFound` if `create_table` has not been called.
+    fn table(&self) -> Result<&Table> {
+        self.table.as_ref().ok_or_else(|| Error::TableNotFound {
+            name: self.table_name.clone(),
+        })
+    }
+
+    /// Returns the table for searching, or `None` if nothing has been indexed yet.
+    ///
+    /// A missing or empty table is the normal state of a fresh install, so searches
+    /// treat it as "no results" rather than an error.
+    async fn searchable_table(&self) -> Result<Option<&Table>> {
+        match self.table.as_ref() {
+            Some(table) if table.count_rows(None).await? > 0 => Ok(Some(table)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Returns the number of stored embeddings, or 0 if the table has not been created.
+    pub async fn count_rows(&self) -> Result<usize> {
+        match self.table.as_ref() {
+            Some(table) => table.count_rows(None).await,
+            None => Ok(0),
+        }
+    }
+
+    /// Returns whether an ANN index exists on the "vector" column.
+    pub async fn has_vector_index(&self) -> Result<bool> {
+        let Some(table) = self.table.as_ref() else {
+            return Ok(false);
+        };
+        let indices = table.list_indices().await?;
+        Ok(indices
+            .iter()
+            .any(|index| index.columns.iter().any(|column| column == "vector")))
+    }
+
     /// Check if the embeddings table exists.
     pub async fn table_exists(&self) -> Result<bool> {
         let tables = self.connection.table_names().execute().await?;
-        Ok(tables.contains(&TABLE_NAME.to_string()))
+        Ok(tables.contains(&self.table_name))
     }
 
-    /// Create a new table with the fixed schema.
+    /// Create a new table with the fixed schema and the default embedding dimension.
+    ///
+    /// See [`create_table_with_dim`](Self::create_table_with_dim).
     pub async fn create_table(&mut self) -> Result<()> {
+        self.create_table_with_dim(EMBEDDING_DIM).await
+    }
+
+    /// Create a new table with the fixed schema and vectors of `dimension` values.
+    ///
+    /// The embedding dimension, distance metric and model name are recorded in the table's
+    /// schema metadata so later opens can validate against them. If the table already
+    /// exists it is opened instead, and its recorded dimension wins.
+    pub async fn create_table_with_dim(&mut self, dimension: usize) -> Result<()> {
         if self.table_exists().await? {
-            self.table = Some(self.connection.open_table(TABLE_NAME).execute().await?);
-            return Ok(());
+            return self.open_existing_table().await;
         }
 
-        let schema = Arc::new(Schema::new(vec![
-            Field::new("id", DataType::Utf8, false),
-            Field::new("title", DataType::Utf8, true),
-            Field::new("path", DataType::Utf8, true),
-            Field::new("content", DataType::Utf8, true),
-            Field::new(
-                "vector",
-                DataType::FixedSizeList(
-                    Arc::new(Field::new("item", DataType::Float32, true)),
-                    EMBEDDING_DIM as i32,
-                ),
-                true,
+        let metadata = HashMap::from([
+            (DIMENSION_METADATA_KEY.to_string(), dimension.to_string()),
+            (
+                DISTANCE_METADATA_KEY.to_string(),
+                distance_name(DistanceType::Cosine).to_string(),
             ),
-        ]));
+            (MODEL_METADATA_KEY.to_string(), MODEL_NAME.to_string()),
+        ]);
+        let schema = Arc::new(Schema::new_with_metadata(
+            vec![
+                Field::new("id", DataType::Utf8, false),
+                Field::new("title", DataType::Utf8, true),
+                Field::new("path", DataType::Utf8, true),
+                Field::new("content", DataType::Utf8, true),
+                Field::new(
+                    "vector",
+                    DataType::FixedSizeList(
+                        Arc::new(Field::new("item", DataType::Float32, true)),
+                        dimension as i32,
+                    ),
+                    true,
+                ),
+            ],
+            metadata,
+        ));
 
         let empty_batch = RecordBatch::try_new(
             schema.clone(),
@@ -115,7 +476,7 @@ impl EmbeddingsStore {
                 Arc::new(
                     FixedSizeListArray::from_iter_primitive::<Float32Type, _, _>(
                         Vec::<Option<Vec<Option<f32>>>>::new(),
-                        EMBEDDING_DIM as i32,
+                        dimension as i32,
                     ),
                 ),
             ],
@@ -125,74 +486,275 @@ impl EmbeddingsStore {
             RecordBatchIterator::new(vec![empty_batch].into_iter().map(Ok), schema.clone());
         let table = self
             .connection
-            .create_table(TABLE_NAME, Box::new(batches))
+            .create_table(&self.table_name, Box::new(batches))
             .execute()
             .await?;
 
         self.table = Some(table);
+        self.dimension = dimension;
+        self.distance = DistanceType::Cosine;
+        self.model = Some(MODEL_NAME.to_string());
         Ok(())
     }
 
     /// Retrieves an existing embedding by its file path.
     pub async fn get_embedding_by_path(&self, path: &str) -> Result<Option<DocumentEmbedding>> {
-        // Use the full-text search API on the "path" field.
-        // Note: This is a heuristic; ensure that your "path" values are unique enough.
-        let embeddings = self.search_text(path, 10).await?;
-        for emb in embeddings {
-            if emb.metadata.path == path {
-                return Ok(Some(emb));
+        let Some(table) = self.searchable_table().await? else {
+            return Ok(None);
+        };
+
+        let mut results = table
+            .query()
+            .only_if(path_predicate(path))
+            .limit(1)
+            .execute()
+            .await?;
+
+        while let Some(batch) = results.try_next().await? {
+            if let Some(embedding) = batch_to_embeddings(&batch, self.dimension)?
+                .into_iter()
+                .next()
+            {
+                return Ok(Some(embedding));
             }
         }
         Ok(None)
     }
 
     pub async fn delete_embedding_by_path(&self, path: &str) -> Result<()> {
-        let table = self.table.as_ref().ok_or(Error::Other {
-            message: "Table not initialized".to_string(),
-            source: None,
-        })?;
-        let predicate = format!("path = '{}'", path);
-        table.delete(&predicate).await?;
+        let table = self.table()?;
+        table.delete(&path_predicate(path)).await?;
         Ok(())
     }
 
-    pub async fn add_embedding(&self, embedding: DocumentEmbedding) -> Result<()> {
+    /// Adds an embedding unless its path is already recorded in SQLite.
+    ///
+    /// The path is normalized (see `utils::normalize_path`) and the embedding's id is
+    /// replaced with [`embedding_id_for_path`] of it.
+    pub async fn add_embedding(&self, mut embedding: DocumentEmbedding) -> Result<()> {
+        embedding.metadata.path = normalize_path(&embedding.metadata.path);
         // Check if the record already exists in SQLite.
-        if dbapi::record_exists(embedding.metadata.path.as_str()).map_err(|e| {
-            lancedb::Error::Other {
-                message: format!("SQLite error: {}", e),
-                source: None,
-            }
-        })? {
-            println!(
+        if dbapi::record_exists_in(&self.db()?, embedding.metadata.path.as_str())
+            .map_err(sqlite_error)?
+        {
+            debug!(
                 "Record already exists in SQLite, skipping insertion: {}",
                 embedding.metadata.path
             );
             return Ok(());
         }
 
-        // Ensure the embedding vector has the expected dimension.
-        if embedding.vector.len() != EMBEDDING_DIM {
-            return Err(Error::InvalidInput {
-                message: format!(
-                    "Embedding vector dimension {} does not match expected {}",
-                    embedding.vector.len(),
-                    EMBEDDING_DIM
-                ),
-            });
+        self.insert_new(vec![embedding]).await
+    }
+
+    /// Stores embeddings whose paths (already normalized) have no SQLite record yet.
+    ///
+    /// The LanceDB rows are written as one batch and the SQLite records in one transaction.
+    async fn insert_new(&self, mut embeddings: Vec<DocumentEmbedding>) -> Result<()> {
+        for embedding in &mut embeddings {
+            embedding.metadata.id = embedding_id_for_path(&embedding.metadata.path);
+        }
+        let documents: Vec<(String, String)> = embeddings
+            .iter()
+            .map(|embedding| {
+                (
+                    embedding.metadata.path.clone(),
+                    embedding.metadata.title.clone(),
+                )
+            })
+            .collect();
+        self.insert_rows(embeddings).await?;
+        self.add_sqlite_records(documents);
+
+        Ok(())
+    }
+
+    /// Adds an embedding, replacing any existing embedding for the same path.
+    ///
+    /// Because the id is derived from the path (see [`embedding_id_for_path`]), re-indexing
+    /// a file always overwrites its previous embedding instead of adding a duplicate. The
+    /// path's pagetable record is inserted or updated with the new title and content hash.
+    pub async fn upsert_embedding(&self, mut embedding: DocumentEmbedding) -> Result<()> {
+        let table = self.table()?;
+        // Validate before deleting, so a rejected embedding doesn't remove the old one.
+        self.check_dimension(&embedding.vector, "Embedding vector")?;
+        // Rows written before ids were derived from paths, or stored under the raw
+        // path, are matched by path rather than id.
+        let predicate = path_predicate(&embedding.metadata.path);
+        embedding.metadata.path = normalize_path(&embedding.metadata.path);
+        embedding.metadata.id = embedding_id_for_path(&embedding.metadata.path);
+        table
+            .delete(&format!(
+                "id = '{}' OR {}",
+                escape_sql_literal(&embedding.metadata.id),
+                predicate
+            ))
+            .await?;
+
+        let (path, title) = (
+            embedding.metadata.path.clone(),
+            embedding.metadata.title.clone(),
+        );
+        self.insert_rows(vec![embedding]).await?;
+        self.upsert_sqlite_record(path, title);
+
+        Ok(())
+    }
+
+    /// Records an embedded document in the store's SQLite pagetable, refreshing the title,
+    /// timestamp and content hash of an existing record so `dbapi::has_changed` sees the
+    /// re-indexed content.
+    ///
+    /// Failures are logged rather than returned, since the embedding itself was stored.
+    fn upsert_sqlite_record(&self, path: String, title: String) {
+        let record = pagetable_record(path, title);
+        let result = self.db().and_then(|conn| {
+            if dbapi::record_exists_in(&conn, &record.lpath).map_err(sqlite_error)? {
+                let update = dbapi::RecordUpdate {
+                    title: Some(record.title.clone()),
+                    timestamp: Some(record.timestamp.clone()),
+                    content_hash: record.content_hash.clone(),
+                    ..Default::default()
+                };
+                dbapi::update_record_in(
+                    &conn,
+                    dbapi::RecordIdentifier::Lpath(record.lpath.clone()),
+                    update,
+                )
+                .map_err(sqlite_error)?;
+                debug!("Updated record in SQLite DB: {}", record.lpath);
+            } else {
+                dbapi::add_record_in(&conn, &record).map_err(sqlite_error)?;
+                debug!("Inserted record into SQLite DB: {}", record.lpath);
+            }
+            Ok(())
+        });
+        if let Err(e) = result {
+            error!("Failed to upsert record into SQLite DB: {}", e);
+        }
+    }
+
+    /// Records embedded documents, given as `(path, title)`, in the store's SQLite
+    /// pagetable, using one connection and one transaction.
+    ///
+    /// Failures are logged rather than returned, since the embeddings themselves were stored.
+    fn add_sqlite_records(&self, documents: Vec<(String, String)>) {
+        if documents.is_empty() {
+            return;
+        }
+        let result = self.db().and_then(|mut conn| {
+            let tx = conn
+                .transaction()
+                .map_err(|e| sqlite_error(dbapi::DbError::from(e)))?;
+            for (path, title) in documents {
+                let record = pagetable_record(path, title);
+                match dbapi::add_record_in(&tx, &record).map_err(sqlite_error)? {
+                    dbapi::AddRecordStatus::Inserted => {
+                        debug!("Inserted record into SQLite DB: {}", record.lpath)
+                    }
+                    dbapi::AddRecordStatus::AlreadyExists => {
+                        debug!("Record already exists in SQLite DB: {}", record.lpath)
+                    }
+                }
+            }
+            tx.commit()
+                .map_err(|e| sqlite_error(dbapi::DbError::from(e)))
+        });
+        if let Err(e) = result {
+            error!("Failed to insert records into SQLite DB: {}", e);
+        }
+    }
+
+    /// Rebuilds the embedding of a single file.
+    ///
+    /// Reads the file at `path`, strips its frontmatter, embeds the body and replaces any
+    /// existing embedding for that path. The title is the file stem. This is the per-file
+    /// counterpart to bulk indexing, meant for watch modes and save hooks.
+    ///
+    /// Files that look binary (see `utils::looks_binary`) are skipped with a warning.
+    ///
+    /// This loads the local model on every call; to reindex many files, keep one
+    /// [`LocalEmbedder`] and call [`reindex_file_with`](Self::reindex_file_with).
+    pub async fn reindex_file(
+        &self,
+        path: &str,
+    ) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.reindex_file_with(&LocalEmbedder::new(), path).await
+    }
+
+    /// Like [`reindex_file`](Self::reindex_file), but embeds with `embedder`, e.g. a
+    /// remote embedding service.
+    pub async fn reindex_file_with(
+        &self,
+        embedder: &dyn Embedder,
+        path: &str,
+    ) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let content = match utils::read_file(Some(path), None, false) {
+            Ok(content) => content,
+            Err(e @ utils::ReadError::Binary { .. }) => {
+                warn!("{}", e);
+                return Ok(());
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let vector = embedder
+            .embed(&[content.as_str()])
+            .await?
+            .into_iter()
+            .next()
+            .ok_or("model returned no embedding")?;
+        let title = Path::new(path)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        let embedding = DocumentEmbedding {
+            vector,
+            metadata: EmbeddingMetadata {
+                id: String::new(),
+                title,
+                path: path.to_string(),
+            },
+            content,
+        };
+        self.upsert_embedding(embedding).await?;
+        Ok(())
+    }
+
+    /// Writes embedding rows to LanceDB as one batch, so the table gets a single new version.
+    async fn insert_rows(&self, embeddings: Vec<DocumentEmbedding>) -> Result<()> {
+        if embeddings.is_empty() {
+            return Ok(());
+        }
+        // Ensure every embedding vector has the expected dimension.
+        for embedding in &embeddings {
+            self.check_dimension(&embedding.vector, "Embedding vector")?;
         }
 
         // Prepare the columns for the record batch.
-        let id = Arc::new(StringArray::from(vec![embedding.metadata.id.clone()]));
-        let title = Arc::new(StringArray::from(vec![embedding.metadata.title.clone()]));
-        let path = Arc::new(StringArray::from(vec![embedding.metadata.path.clone()]));
-        let content = Arc::new(StringArray::from(vec![embedding.content]));
+        let count = embeddings.len();
+        let mut ids = Vec::with_capacity(count);
+        let mut titles = Vec::with_capacity(count);
+        let mut paths = Vec::with_capacity(count);
+        let mut contents = Vec::with_capacity(count);
+        let mut vectors = Vec::with_capacity(count);
+        for embedding in embeddings {
+            ids.push(embedding.metadata.id);
+            titles.push(embedding.metadata.title);
+            paths.push(embedding.metadata.path);
+            contents.push(embedding.content);
+            vectors.push(Some(
+                embedding.vector.into_iter().map(Some).collect::<Vec<_>>(),
+            ));
+        }
+        let id = Arc::new(StringArray::from(ids));
+        let title = Arc::new(StringArray::from(titles));
+        let path = Arc::new(StringArray::from(paths));
+        let content = Arc::new(StringArray::from(contents));
         let vector = Arc::new(
             FixedSizeListArray::from_iter_primitive::<Float32Type, _, _>(
-                vec![Some(
-                    embedding.vector.into_iter().map(Some).collect::<Vec<_>>(),
-                )],
-                EMBEDDING_DIM as i32,
+                vectors,
+                self.dimension as i32,
             ),
         );
 
@@ -206,71 +768,124 @@ impl EmbeddingsStore {
 
         let schema_ref: SchemaRef = batch.schema();
         let iter = RecordBatchIterator::new(vec![batch].into_iter().map(Ok), schema_ref);
-        self.table
-            .as_ref()
-            .ok_or(Error::Other {
-                message: "Table not initialized".to_string(),
-                source: None,
-            })?
-            .add(Box::new(iter))
-            .execute()
-            .await?;
-        println!("Added record to LanceDB: {}", embedding.metadata.path);
-
-        // Add the record to SQLite.
-        let timestamp = Utc::now().to_rfc3339();
-        let record = dbapi::Record {
-            lpath: embedding.metadata.path,
-            title: embedding.metadata.title,
-            timestamp,
-            // Adjust vpath as needed. Here we use an empty string if not applicable.
-            vpath: "".to_string(),
-            project: None,
-        };
-        match dbapi::add_record(&record) {
-            Ok(status) => match status {
-                dbapi::AddRecordStatus::Inserted => {
-                    println!("Inserted record into SQLite DB: {}", record.lpath)
-                }
-                dbapi::AddRecordStatus::AlreadyExists => {
-                    println!("Record already exists in SQLite DB: {}", record.lpath)
-                }
-            },
-            Err(e) => eprintln!("Failed to insert record into SQLite DB: {}", e),
-        }
-
+        self.table()?.add(Box::new(iter)).execute().await?;
+        debug!("Added {} records to LanceDB", count);
         Ok(())
     }
 
     /// Add multiple document embeddings to the store.
+    ///
+    /// Like [`add_embedding`](Self::add_embedding), embeddings whose path already has a
+    /// SQLite record are skipped, as are repeated paths within the batch. The remaining
+    /// embeddings are written in one LanceDB batch and one SQLite transaction.
     pub async fn add_embeddings(&self, embeddings: Vec<DocumentEmbedding>) -> Result<()> {
         if embeddings.is_empty() {
             return Ok(());
         }
 
-        let _table = self.table.as_ref().ok_or(Error::Other {
-            message: "Table not initialized".to_string(),
-            source: None,
-        })?;
+        let _table = self.table()?;
 
-        for embedding in embeddings {
-            self.add_embedding(embedding).await?;
+        // Check every path against SQLite in one go rather than once per embedding.
+        let paths: Vec<String> = embeddings
+            .iter()
+            .map(|embedding| normalize_path(&embedding.metadata.path))
+            .collect();
+        let path_refs: Vec<&str> = paths.iter().map(String::as_str).collect();
+        let existing = dbapi::records_exist_in(&self.db()?, &path_refs).map_err(sqlite_error)?;
+
+        let mut seen = HashSet::new();
+        let mut new_embeddings = Vec::new();
+        for (mut embedding, path) in embeddings.into_iter().zip(paths) {
+            if existing.get(&path).copied().unwrap_or(false) || !seen.insert(path.clone()) {
+                debug!(
+                    "Record already exists in SQLite, skipping insertion: {}",
+                    path
+                );
+                continue;
+            }
+            embedding.metadata.path = path;
+            new_embeddings.push(embedding);
+        }
+        self.insert_new(new_embeddings).await
+    }
+
+    /// Deletes every embedding by dropping and recreating the table.
+    ///
+    /// The SQLite pagetable rows that were added for the stored embeddings are deleted too,
+    /// in one transaction. Returns the number of embeddings removed.
+    pub async fn clear(&mut self) -> Result<usize> {
+        let paths = self.stored_paths().await?;
+        if self.table_exists().await? {
+            self.connection.drop_table(&self.table_name).await?;
+        }
+        self.table = None;
+        // Recreate at the same dimension so clearing doesn't change the table's shape.
+        self.create_table_with_dim(self.dimension).await?;
+        self.delete_sqlite_records(&paths)?;
+        Ok(paths.len())
+    }
+
+    /// Deletes the pagetable records for `paths` in one transaction.
+    fn delete_sqlite_records(&self, paths: &[String]) -> Result<()> {
+        let mut conn = self.db()?;
+        let tx = conn
+            .transaction()
+            .map_err(|e| sqlite_error(dbapi::DbError::from(e)))?;
+        for path in paths {
+            dbapi::delete_record_in(&tx, dbapi::RecordIdentifier::Lpath(path.clone()))
+                .map_err(sqlite_error)?;
+        }
+        tx.commit()
+            .map_err(|e| sqlite_error(dbapi::DbError::from(e)))
+    }
+
+    /// Returns the path of every stored embedding.
+    async fn stored_paths(&self) -> Result<Vec<String>> {
+        let Some(table) = self.table.as_ref() else {
+            return Ok(Vec::new());
+        };
+        let row_count = table.count_rows(None).await?;
+        if row_count == 0 {
+            return Ok(Vec::new());
+        }
+        let mut results = table
+            .query()
+            .select(Select::Columns(vec!["path".to_string()]))
+            .limit(row_count)
+            .execute()
+            .await?;
+
+        let mut paths = Vec::with_capacity(row_count);
+        while let Some(batch) = results.try_next().await? {
+            let column = string_column(&batch, "path")?;
+            paths.extend((0..batch.num_rows()).map(|i| column.value(i).to_string()));
+        }
+        Ok(paths)
+    }
+
+    /// Removes the ANN index from the "vector" column, keeping all stored data.
+    ///
+    /// Searches fall back to a brute-force scan until [`create_index`](Self::create_index)
+    /// is called again. Does nothing if there is no vector index.
+    pub async fn drop_index(&self) -> Result<()> {
+        let table = self.table()?;
+        for index in table.list_indices().await? {
+            if index.columns.iter().any(|column| column == "vector") {
+                table.drop_index(&index.name).await?;
+            }
         }
         Ok(())
     }
 
     /// Create an approximate nearest neighbor (ANN) index for faster vector search.
     pub async fn create_index(&self) -> Result<()> {
-        let table = self.table.as_ref().ok_or(Error::Other {
-            message: "Table not initialized".to_string(),
-            source: None,
-        })?;
+        let table = self.table()?;
         table
             .create_index(
                 &["vector"],
                 Index::IvfPq(
                     IvfPqIndexBuilder::default()
-                        .distance_type(DistanceType::Cosine)
+                        .distance_type(self.distance)
                         .num_partitions(5)
                         .num_sub_vectors(16),
                 ),
@@ -282,10 +897,7 @@ impl EmbeddingsStore {
 
     /// Create a full text search (FTS) index on the "content" field.
     pub async fn create_text_index(&self) -> Result<()> {
-        let table = self.table.as_ref().ok_or(Error::Other {
-            message: "Table not initialized".to_string(),
-            source: None,
-        })?;
+        let table = self.table()?;
         table
             .create_index(&["content"], Index::FTS(FtsIndexBuilder::default()))
             .execute()
@@ -293,204 +905,916 @@ impl EmbeddingsStore {
         Ok(())
     }
 
+    /// Deletes everything stored for `project`: its pagetable records and their embeddings.
+    ///
+    /// This is the destructive counterpart to re-importing a project. Embeddings are
+    /// matched by the lpaths of the project's records and deleted first, in one delete;
+    /// the records are only deleted afterwards, so if deleting embeddings fails the reset
+    /// can simply be retried.
+    pub async fn reset_project(&self, project: &str) -> Result<ResetCounts> {
+        let lpaths = dbapi::project_lpaths_in(&self.db()?, project).map_err(sqlite_error)?;
+
+        let mut embeddings = 0;
+        if let Some(table) = self.table.as_ref().filter(|_| !lpaths.is_empty()) {
+            let before = table.count_rows(None).await?;
+            table.delete(&paths_predicate(&lpaths)).await?;
+            embeddings = before.saturating_sub(table.count_rows(None).await?);
+        }
+
+        let records =
+            dbapi::delete_project_records_in(&self.db()?, project).map_err(sqlite_error)?;
+        Ok(ResetCounts {
+            records: records.len(),
+            embeddings,
+        })
+    }
+
+    /// Lists stored embeddings without a query vector, `limit` rows starting at `offset`.
+    ///
+    /// Page through the whole table by increasing `offset` until fewer than `limit` rows
+    /// come back, e.g. to audit the store or migrate it to a new model. Returns an empty
+    /// `Vec` if the table has not been created or has no rows yet.
+    pub async fn list(&self, offset: usize, limit: usize) -> Result<Vec<DocumentEmbedding>> {
+        let Some(table) = self.searchable_table().await? else {
+            return Ok(Vec::new());
+        };
+
+        let mut results = table.query().offset(offset).limit(limit).execute().await?;
+
+        let mut embeddings = Vec::new();
+        while let Some(batch) = results.try_next().await? {
+            embeddings.extend(batch_to_embeddings(&batch, self.dimension)?);
+        }
+        Ok(embeddings)
+    }
+
     /// Search for similar embeddings based on vector similarity.
+    ///
+    /// Returns an empty `Vec` if the table has not been created or has no rows yet.
     pub async fn search(
         &self,
         query_vector: &[f32],
         limit: usize,
     ) -> Result<Vec<DocumentEmbedding>> {
-        let table = self.table.as_ref().ok_or(Error::Other {
-            message: "Table not initialized".to_string(),
-            source: None,
-        })?;
+        self.search_with_raw_filter(query_vector, limit, None).await
+    }
 
-        if query_vector.len() != EMBEDDING_DIM {
-            return Err(Error::InvalidInput {
-                message: format!(
-                    "Query vector dimension {} does not match expected {}",
-                    query_vector.len(),
-                    EMBEDDING_DIM
-                ),
-            });
+    /// Search for similar embeddings, restricted to rows matching `filter`.
+    pub async fn search_filtered(
+        &self,
+        query_vector: &[f32],
+        limit: usize,
+        filter: &MetadataFilter,
+    ) -> Result<Vec<DocumentEmbedding>> {
+        self.search_with_raw_filter(query_vector, limit, Some(filter.as_predicate()))
+            .await
+    }
+
+    /// Search for similar embeddings using a raw LanceDB SQL predicate.
+    ///
+    /// **Advanced:** the predicate is passed to LanceDB verbatim. Never build it from
+    /// user input; use [`MetadataFilter`] with [`search_filtered`](Self::search_filtered) instead.
+    pub async fn search_with_raw_filter(
+        &self,
+        query_vector: &[f32],
+        limit: usize,
+        predicate: Option<&str>,
+    ) -> Result<Vec<DocumentEmbedding>> {
+        let Some(table) = self.searchable_table().await? else {
+            return Ok(Vec::new());
+        };
+
+        let mut embeddings = Vec::new();
+        for batch in self
+            .vector_search_batches(table, query_vector, limit, predicate)
+            .await?
+        {
+            embeddings.extend(batch_to_embeddings(&batch, self.dimension)?);
         }
+        Ok(embeddings)
+    }
 
-        let mut results = table
+    /// Search for similar embeddings, returning each match's content, metadata and score.
+    ///
+    /// Hits are ordered by relevance, most similar first. The score is derived from the
+    /// table's distance metric and lies in `[0, 1]`, higher being more similar (for cosine
+    /// tables it is the cosine similarity, clamped at 0). Returns an empty `Vec` if the table has no rows yet.
+    pub async fn search_hits(
+        &self,
+        query_vector: &[f32],
+        limit: usize,
+        filter: Option<&MetadataFilter>,
+    ) -> Result<Vec<SearchHit>> {
+        let Some(table) = self.searchable_table().await? else {
+            return Ok(Vec::new());
+        };
+
+        let predicate = filter.map(MetadataFilter::as_predicate);
+        let mut hits = Vec::new();
+        for batch in self
+            .vector_search_batches(table, query_vector, limit, predicate)
+            .await?
+        {
+            let distances = batch
+                .column_by_name("_distance")
+                .and_then(|col| col.as_any().downcast_ref::<Float32Array>())
+                .ok_or_else(|| Error::Other {
+                    message: "Failed to get _distance column".to_string(),
+                    source: None,
+                })?;
+            let embeddings = batch_to_embeddings(&batch, self.dimension)?;
+            for (row_idx, embedding) in embeddings.into_iter().enumerate() {
+                hits.push(SearchHit {
+                    content: embedding.content,
+                    metadata: embedding.metadata,
+                    score: distance_to_score(distances.value(row_idx), self.distance),
+                });
+            }
+        }
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+        Ok(hits)
+    }
+
+    /// Runs a vector search against `table` and collects the result batches.
+    async fn vector_search_batches(
+        &self,
+        table: &Table,
+        query_vector: &[f32],
+        limit: usize,
+        predicate: Option<&str>,
+    ) -> Result<Vec<RecordBatch>> {
+        self.check_dimension(query_vector, "Query vector")?;
+
+        let mut query = table
             .vector_search(query_vector)?
-            .distance_type(DistanceType::Cosine)
+            .distance_type(self.distance)
+            .limit(limit);
+        if let Some(predicate) = predicate {
+            query = query.only_if(predicate);
+        }
+        let mut results = query.execute().await?;
+
+        let mut batches = Vec::new();
+        while let Some(batch) = results.try_next().await? {
+            batches.push(batch);
+        }
+        Ok(batches)
+    }
+
+    /// Search for records using full text search on the "content" field.
+    ///
+    /// The query is trimmed; an empty or whitespace-only query matches nothing. Returns an
+    /// empty `Vec` if the table has not been created or has no rows yet.
+    pub async fn search_text(&self, query: &str, limit: usize) -> Result<Vec<DocumentEmbedding>> {
+        let query = query.trim();
+        if query.is_empty() {
+            return Ok(Vec::new());
+        }
+        let Some(table) = self.searchable_table().await? else {
+            return Ok(Vec::new());
+        };
+
+        let mut results = table
+            .query()
+            .full_text_search(FullTextSearchQuery::new(query.to_owned()))
             .limit(limit)
             .execute()
             .await?;
 
         let mut embeddings = Vec::new();
         while let Some(batch) = results.try_next().await? {
-            for row_idx in 0..batch.num_rows() {
-                let id = batch
-                    .column_by_name("id")
-                    .and_then(|col| col.as_any().downcast_ref::<StringArray>())
-                    .ok_or_else(|| Error::Other {
-                        message: "Failed to get id column".to_string(),
-                        source: None,
-                    })?
-                    .value(row_idx)
-                    .to_string();
-
-                let title = batch
-                    .column_by_name("title")
-                    .and_then(|col| col.as_any().downcast_ref::<StringArray>())
-                    .ok_or_else(|| Error::Other {
-                        message: "Failed to get title column".to_string(),
-                        source: None,
-                    })?
-                    .value(row_idx)
-                    .to_string();
-
-                let path = batch
-                    .column_by_name("path")
-                    .and_then(|col| col.as_any().downcast_ref::<StringArray>())
-                    .ok_or_else(|| Error::Other {
-                        message: "Failed to get path column".to_string(),
-                        source: None,
-                    })?
-                    .value(row_idx)
-                    .to_string();
-
-                let content = batch
-                    .column_by_name("content")
-                    .and_then(|col| col.as_any().downcast_ref::<StringArray>())
-                    .ok_or_else(|| Error::Other {
-                        message: "Failed to get content column".to_string(),
-                        source: None,
-                    })?
-                    .value(row_idx)
-                    .to_string();
-
-                let vector_col = batch
-                    .column_by_name("vector")
-                    .and_then(|col| col.as_any().downcast_ref::<FixedSizeListArray>())
-                    .ok_or_else(|| Error::Other {
-                        message: "Failed to get vector column".to_string(),
-                        source: None,
-                    })?;
-
-                let vector_values: Vec<f32> = (0..EMBEDDING_DIM)
-                    .map(|i| {
-                        let list_value = vector_col.value(row_idx);
-                        if i < list_value.len() {
-                            if let Some(float_array) = list_value
-                                .as_any()
-                                .downcast_ref::<arrow_array::Float32Array>()
-                            {
-                                return float_array.value(i);
-                            }
-                        }
-                        0.0
-                    })
-                    .collect();
-
-                embeddings.push(DocumentEmbedding {
-                    vector: vector_values,
-                    metadata: EmbeddingMetadata { id, title, path },
-                    content,
-                });
-            }
+            embeddings.extend(batch_to_embeddings(&batch, self.dimension)?);
         }
         Ok(embeddings)
     }
 
-    /// Search for records using full text search on the "content" field.
-    pub async fn search_text(&self, query: &str, limit: usize) -> Result<Vec<DocumentEmbedding>> {
-        let table = self.table.as_ref().ok_or(Error::Other {
-            message: "Table not initialized".to_string(),
-            source: None,
-        })?;
+    /// Full text search on the "content" field that returns each hit's metadata, content
+    /// and BM25 relevance score, highest score first.
+    ///
+    /// Unlike [`search_text`](Self::search_text), vectors are neither fetched nor decoded,
+    /// which makes keyword search cheaper. Like `search_text`, empty queries match nothing.
+    /// Returns an empty `Vec` if the table has not been created or has no rows yet.
+    pub async fn search_text_scored(
+        &self,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<(EmbeddingMetadata, String, f32)>> {
+        let query = query.trim();
+        if query.is_empty() {
+            return Ok(Vec::new());
+        }
+        let Some(table) = self.searchable_table().await? else {
+            return Ok(Vec::new());
+        };
 
+        let columns = ["id", "title", "path", "content"];
         let mut results = table
             .query()
             .full_text_search(FullTextSearchQuery::new(query.to_owned()))
+            .select(Select::Columns(
+                columns.iter().map(|c| c.to_string()).collect(),
+            ))
             .limit(limit)
             .execute()
             .await?;
 
-        let mut embeddings = Vec::new();
+        let mut hits = Vec::new();
         while let Some(batch) = results.try_next().await? {
+            let ids = string_column(&batch, "id")?;
+            let titles = string_column(&batch, "title")?;
+            let paths = string_column(&batch, "path")?;
+            let contents = string_column(&batch, "content")?;
+            let scores = batch
+                .column_by_name("_score")
+                .and_then(|col| col.as_any().downcast_ref::<Float32Array>())
+                .ok_or_else(|| Error::Other {
+                    message: "Failed to get _score column".to_string(),
+                    source: None,
+                })?;
             for row_idx in 0..batch.num_rows() {
-                let id = batch
-                    .column_by_name("id")
-                    .and_then(|col| col.as_any().downcast_ref::<StringArray>())
-                    .ok_or_else(|| Error::Other {
-                        message: "Failed to get id column".to_string(),
-                        source: None,
-                    })?
-                    .value(row_idx)
-                    .to_string();
-
-                let title = batch
-                    .column_by_name("title")
-                    .and_then(|col| col.as_any().downcast_ref::<StringArray>())
-                    .ok_or_else(|| Error::Other {
-                        message: "Failed to get title column".to_string(),
-                        source: None,
-                    })?
-                    .value(row_idx)
-                    .to_string();
-
-                let path = batch
-                    .column_by_name("path")
-                    .and_then(|col| col.as_any().downcast_ref::<StringArray>())
-                    .ok_or_else(|| Error::Other {
-                        message: "Failed to get path column".to_string(),
-                        source: None,
-                    })?
-                    .value(row_idx)
-                    .to_string();
-
-                let content = batch
-                    .column_by_name("content")
-                    .and_then(|col| col.as_any().downcast_ref::<StringArray>())
-                    .ok_or_else(|| Error::Other {
-                        message: "Failed to get content column".to_string(),
-                        source: None,
-                    })?
-                    .value(row_idx)
-                    .to_string();
-
-                let vector_col = batch
-                    .column_by_name("vector")
-                    .and_then(|col| col.as_any().downcast_ref::<FixedSizeListArray>())
-                    .ok_or_else(|| Error::Other {
-                        message: "Failed to get vector column".to_string(),
-                        source: None,
-                    })?;
-
-                let vector_values: Vec<f32> = (0..EMBEDDING_DIM)
-                    .map(|i| {
-                        let list_value = vector_col.value(row_idx);
-                        if i < list_value.len() {
-                            if let Some(float_array) = list_value
-                                .as_any()
-                                .downcast_ref::<arrow_array::Float32Array>()
-                            {
-                                return float_array.value(i);
-                            }
-                        }
-                        0.0
-                    })
-                    .collect();
-
-                embeddings.push(DocumentEmbedding {
-                    vector: vector_values,
-                    metadata: EmbeddingMetadata { id, title, path },
-                    content,
-                });
+                let metadata = EmbeddingMetadata {
+                    id: ids.value(row_idx).to_string(),
+                    title: titles.value(row_idx).to_string(),
+                    path: paths.value(row_idx).to_string(),
+                };
+                hits.push((
+                    metadata,
+                    contents.value(row_idx).to_string(),
+                    scores.value(row_idx),
+                ));
             }
         }
-        Ok(embeddings)
+        hits.sort_by(|a, b| b.2.total_cmp(&a.2));
+        Ok(hits)
+    }
+}
+
+/// Converts a LanceDB `_distance` into a similarity score in `[0, 1]`, where higher is
+/// more similar.
+///
+/// Cosine and dot distances are `1 - similarity`, so they are inverted; L2 distances are
+/// mapped with `1 / (1 + d)`. The result is clamped to `[0, 1]`, since unnormalized
+/// vectors can push a similarity below 0 or above 1, and it can be shown as a percentage.
+fn distance_to_score(distance: f32, distance_type: DistanceType) -> f32 {
+    let score = match distance_type {
+        DistanceType::L2 => 1.0 / (1.0 + distance),
+        _ => 1.0 - distance,
+    };
+    score.clamp(0.0, 1.0)
+}
+
+#[async_trait]
+impl VectorStore for EmbeddingsStore {
+    type Error = Error;
+
+    async fn add(&self, embedding: DocumentEmbedding) -> Result<()> {
+        self.add_embedding(embedding).await
+    }
+
+    async fn search(&self, query_vector: &[f32], limit: usize) -> Result<Vec<SearchHit>> {
+        self.search_hits(query_vector, limit, None).await
+    }
+
+    async fn delete_by_path(&self, path: &str) -> Result<()> {
+        self.delete_embedding_by_path(path).await
+    }
+
+    async fn count(&self) -> Result<usize> {
+        self.count_rows().await
+    }
+
+    async fn create_index(&self) -> Result<()> {
+        EmbeddingsStore::create_index(self).await
+    }
+}
+
+/// Returns the string column `name` of a result batch.
+fn string_column<'a>(batch: &'a RecordBatch, name: &str) -> Result<&'a StringArray> {
+    batch
+        .column_by_name(name)
+        .and_then(|col| col.as_any().downcast_ref::<StringArray>())
+        .ok_or_else(|| Error::Other {
+            message: format!("Failed to get {} column", name),
+            source: None,
+        })
+}
+
+/// Converts a result batch into embeddings.
+///
+/// Every stored vector must have exactly `dimension` values; a mismatch means vectors
+/// from different models were mixed, so it is reported instead of padded or truncated.
+fn batch_to_embeddings(batch: &RecordBatch, dimension: usize) -> Result<Vec<DocumentEmbedding>> {
+    let ids = string_column(batch, "id")?;
+    let titles = string_column(batch, "title")?;
+    let paths = string_column(batch, "path")?;
+    let contents = string_column(batch, "content")?;
+    let vector_col = batch
+        .column_by_name("vector")
+        .and_then(|col| col.as_any().downcast_ref::<FixedSizeListArray>())
+        .ok_or_else(|| Error::Other {
+            message: "Failed to get vector column".to_string(),
+            source: None,
+        })?;
+
+    let mut embeddings = Vec::with_capacity(batch.num_rows());
+    for row_idx in 0..batch.num_rows() {
+        let list_value = vector_col.value(row_idx);
+        let floats = list_value
+            .as_any()
+            .downcast_ref::<Float32Array>()
+            .ok_or_else(|| Error::Other {
+                message: "Vector column does not contain f32 values".to_string(),
+                source: None,
+            })?;
+        if floats.len() != dimension {
+            return Err(Error::Other {
+                message: format!(
+                    "Stored vector has dimension {} but the table expects {}",
+                    floats.len(),
+                    dimension
+                ),
+                source: None,
+            });
+        }
+
+        embeddings.push(DocumentEmbedding {
+            vector: floats.values().to_vec(),
+            metadata: EmbeddingMetadata {
+                id: ids.value(row_idx).to_string(),
+                title: titles.value(row_idx).to_string(),
+                path: paths.value(row_idx).to_string(),
+            },
+            content: contents.value(row_idx).to_string(),
+        });
+    }
+    Ok(embeddings)
+}
+
+/// Returns the stable embedding id for a document path.
+///
+/// The id is a UUIDv5 of the normalized physical path (see `utils::normalize_path`), so
+/// it does not change when a note's title or virtual path changes.
+pub fn embedding_id_for_path(path: &str) -> String {
+    Uuid::new_v5(&Uuid::NAMESPACE_URL, normalize_path(path).as_bytes()).to_string()
+}
+
+/// Builds the pagetable record for an embedded document, hashing the file at `path`.
+fn pagetable_record(path: String, title: String) -> dbapi::Record {
+    dbapi::Record {
+        content_hash: utils::hash_file(&path).ok(),
+        lpath: path,
+        title,
+        timestamp: Utc::now().to_rfc3339(),
+        // Adjust vpath as needed. Here we use an empty string if not applicable.
+        vpath: "".to_string(),
+        project: None,
+    }
+}
+
+/// Wraps a pagetable error as a LanceDB error, for store methods that touch both.
+fn sqlite_error(e: dbapi::DbError) -> Error {
+    Error::Other {
+        message: format!("SQLite error: {}", e),
+        source: None,
+    }
+}
+
+/// Returns the LanceDB directory used by [`EmbeddingsStore::new`], inside the config directory.
+pub fn embeddings_dir() -> PathBuf {
+    confapi::get_config_dir().join("embeddings")
+}
+
+/// Returns the embeddings table name from the configuration.
+///
+/// This is `<ai.embeddings.table_prefix>_embeddings` when a non-empty prefix is configured,
+/// and `embeddings` otherwise (including when the config can't be read).
+pub fn configured_table_name() -> String {
+    let prefix = confapi::get_config().ok().and_then(|config| {
+        config
+            .ai
+            .and_then(|ai| ai.embeddings)
+            .and_then(|embeddings| embeddings.table_prefix)
+    });
+    match prefix.filter(|p| !p.is_empty()) {
+        Some(prefix) => format!("{}_{}", prefix, TABLE_NAME),
+        None => TABLE_NAME.to_string(),
     }
 }
 
 /// Helper function to create a new embeddings store with a table.
+///
+/// A newly created table is sized to the configured embedder's dimension (see
+/// `ai::embedder::embedder_from_config`). If that can't be determined, e.g. because the
+/// local model isn't installed yet, the default dimension is used and a warning is logged.
 pub async fn create_store() -> Result<EmbeddingsStore> {
     let mut store = EmbeddingsStore::new().await?;
-    store.create_table().await?;
+    store
+        .create_table_with_dim(configured_dimension().await)
+        .await?;
     Ok(store)
 }
+
+/// Returns the configured embedder's dimension, or [`EMBEDDING_DIM`] if it is unknown.
+async fn configured_dimension() -> usize {
+    let embedder = confapi::get_config()
+        .and_then(|config| embedder_from_config(&config))
+        .map_err(|e| e.to_string());
+    let dimension = match embedder {
+        Ok(embedder) => embedder.dimension().await.map_err(|e| e.to_string()),
+        Err(e) => Err(e),
+    };
+    dimension.unwrap_or_else(|e| {
+        warn!(
+            "Could not determine the embedding dimension, using {}: {}",
+            EMBEDDING_DIM, e
+        );
+        EMBEDDING_DIM
+    })
+}
+
+/// Searches the notes semantically for `query` and returns the matching pagetable records.
+///
+/// The query text is embedded with `embedder`, which must be the embedder that produced the
+/// store's vectors (see `ai::embedder::embedder_from_config`), and compared against `store`.
+/// At most `limit` notes scoring at least `threshold` are returned, most similar first,
+/// each with its score (see [`EmbeddingsStore::search_hits`]). Records are read from the
+/// store's database, and hits without a pagetable record are skipped.
+pub async fn semantic_search(
+    store: &EmbeddingsStore,
+    embedder: &dyn Embedder,
+    query: &str,
+    limit: usize,
+    threshold: f32,
+) -> std::result::Result<Vec<(dbapi::Record, f32)>, Box<dyn std::error::Error + Send + Sync>> {
+    let query_vector = embedder
+        .embed(&[query])
+        .await?
+        .into_iter()
+        .next()
+        .ok_or("model returned no embedding")?;
+
+    let hits = store.search_hits(&query_vector, limit, None).await?;
+    let conn = store.db()?;
+    let mut results = Vec::new();
+    for hit in hits {
+        if hit.score < threshold {
+            continue;
+        }
+        match dbapi::get_record_in(&conn, &hit.metadata.path)? {
+            Some(record) => results.push((record, hit.score)),
+            None => debug!("No SQLite record for embedding at {}", hit.metadata.path),
+        }
+    }
+    Ok(results)
+}
+
+/// Like [`create_store`], but sizes a newly created table to the local model's native
+/// embedding dimension, and fails instead of falling back to the default when it can't be
+/// determined.
+///
+/// This loads the model once to ask for its output size, so prefer it when switching models.
+pub async fn create_store_for_model() -> Result<EmbeddingsStore> {
+    let dimension = sentence_transformer::embedding_dim().map_err(|e| Error::Other {
+        message: format!("Failed to determine the model's embedding dimension: {}", e),
+        source: None,
+    })?;
+    let mut store = EmbeddingsStore::new().await?;
+    store.create_table_with_dim(dimension).await?;
+    Ok(store)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metadata_filter_escapes_quotes() {
+        let filter = MetadataFilter::eq(FilterColumn::Path, "/notes/john's ideas.md");
+        assert_eq!(filter.as_predicate(), "path = '/notes/john''s ideas.md'");
+
+        let injected = MetadataFilter::eq(FilterColumn::Title, "x' OR '1'='1");
+        assert_eq!(injected.as_predicate(), "title = 'x'' OR ''1''=''1'");
+    }
+
+    #[test]
+    fn test_embedding_id_is_stable_across_path_spellings() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let file = dir.path().join("note.md");
+        std::fs::write(&file, "body").unwrap();
+
+        let direct = file.to_str().unwrap().to_string();
+        let dotted = dir.path().join(".").join("note.md");
+        assert_eq!(
+            embedding_id_for_path(&direct),
+            embedding_id_for_path(dotted.to_str().unwrap())
+        );
+        assert_eq!(
+            embedding_id_for_path(&direct),
+            embedding_id_for_path(&direct)
+        );
+    }
+
+    /// Opens a store with a table inside `dir`, without touching the config directory.
+    async fn temp_store(dir: &Path) -> EmbeddingsStore {
+        let mut store = EmbeddingsStore::open_at(
+            &dir.join("vectors"),
+            &dir.join("pagetable.sqlite"),
+            TABLE_NAME,
+        )
+        .await
+        .unwrap();
+        store.create_table().await.unwrap();
+        store
+    }
+
+    fn embedding_for(path: &str, title: &str, vector: Vec<f32>) -> DocumentEmbedding {
+        DocumentEmbedding {
+            vector,
+            metadata: EmbeddingMetadata {
+                id: String::new(),
+                title: title.to_string(),
+                path: path.to_string(),
+            },
+            content: "body".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reindexing_same_file_yields_one_embedding() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let store = temp_store(dir.path()).await;
+        let file = dir.path().join("note.md");
+        std::fs::write(&file, "body").unwrap();
+        let path = file.to_str().unwrap();
+
+        for title in ["First title", "Renamed title"] {
+            let embedding = embedding_for(path, title, vec![0.5; EMBEDDING_DIM]);
+            store.upsert_embedding(embedding).await.unwrap();
+        }
+
+        assert_eq!(store.count_rows().await.unwrap(), 1);
+        let found = store.get_embedding_by_path(path).await.unwrap().unwrap();
+        assert_eq!(found.metadata.title, "Renamed title");
+    }
+
+    #[tokio::test]
+    async fn test_rejected_upsert_keeps_existing_embedding() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let store = temp_store(dir.path()).await;
+        let path = dir.path().join("note.md");
+        let path = path.to_str().unwrap();
+
+        let embedding = embedding_for(path, "Kept", vec![0.5; EMBEDDING_DIM]);
+        store.upsert_embedding(embedding).await.unwrap();
+        let wrong = embedding_for(path, "Rejected", vec![0.5; EMBEDDING_DIM + 1]);
+        assert!(store.upsert_embedding(wrong).await.is_err());
+
+        let found = store.get_embedding_by_path(path).await.unwrap().unwrap();
+        assert_eq!(found.metadata.title, "Kept");
+    }
+
+    #[tokio::test]
+    async fn test_upsert_replaces_rows_with_legacy_ids_and_raw_paths() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let store = temp_store(dir.path()).await;
+        let file = dir.path().join("note.md");
+        std::fs::write(&file, "body").unwrap();
+        let raw_path = dir.path().join(".").join("note.md");
+        let raw_path = raw_path.to_str().unwrap();
+
+        // A row stored before ids were derived from paths, under an un-normalized path.
+        let mut legacy = embedding_for(raw_path, "Legacy", vec![0.5; EMBEDDING_DIM]);
+        legacy.metadata.id = "caller-chosen".to_string();
+        store.insert_rows(vec![legacy]).await.unwrap();
+
+        let embedding = embedding_for(raw_path, "Current", vec![0.5; EMBEDDING_DIM]);
+        store.upsert_embedding(embedding).await.unwrap();
+
+        assert_eq!(store.count_rows().await.unwrap(), 1);
+        let listed = store.list(0, 10).await.unwrap();
+        assert_eq!(listed[0].metadata.title, "Current");
+        assert_eq!(listed[0].metadata.id, embedding_id_for_path(raw_path));
+    }
+
+    #[tokio::test]
+    async fn test_upsert_refreshes_pagetable_record() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let store = temp_store(dir.path()).await;
+        let file = dir.path().join("note.md");
+        let path = file.to_str().unwrap();
+        let conn = dbapi::open_db_at(store.db_path()).unwrap();
+
+        let mut hashes = Vec::new();
+        for (title, body) in [("First title", "body"), ("Renamed title", "edited body")] {
+            std::fs::write(&file, body).unwrap();
+            let embedding = embedding_for(path, title, vec![0.5; EMBEDDING_DIM]);
+            store.upsert_embedding(embedding).await.unwrap();
+            let record = dbapi::get_record_in(&conn, path).unwrap().unwrap();
+            hashes.push(record.content_hash);
+        }
+
+        let record = dbapi::get_record_in(&conn, path).unwrap().unwrap();
+        assert_eq!(record.title, "Renamed title");
+        assert_ne!(hashes[0], hashes[1]);
+        // The stored hash matches the file, so it no longer counts as changed.
+        assert_eq!(record.content_hash, Some(utils::hash_file(path).unwrap()));
+    }
+
+    #[tokio::test]
+    async fn test_list_pages_through_embeddings() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let store = temp_store(dir.path()).await;
+        assert!(store.list(0, 10).await.unwrap().is_empty());
+
+        let embeddings = ["a", "b", "c"]
+            .iter()
+            .map(|name| {
+                let path = dir.path().join(format!("{}.md", name));
+                embedding_for(path.to_str().unwrap(), name, vec![0.5; EMBEDDING_DIM])
+            })
+            .collect();
+        store.add_embeddings(embeddings).await.unwrap();
+
+        assert_eq!(store.list(0, 2).await.unwrap().len(), 2);
+        assert_eq!(store.list(2, 2).await.unwrap().len(), 1);
+        assert!(store.list(3, 2).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_reset_project_deletes_embeddings_then_records() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let store = temp_store(dir.path()).await;
+        let paths: Vec<String> = ["a", "b", "c"]
+            .iter()
+            .map(|name| {
+                dir.path()
+                    .join(format!("{}.md", name))
+                    .to_str()
+                    .unwrap()
+                    .to_string()
+            })
+            .collect();
+        let embeddings = paths
+            .iter()
+            .map(|path| embedding_for(path, "Note", vec![0.5; EMBEDDING_DIM]))
+            .collect();
+        store.add_embeddings(embeddings).await.unwrap();
+
+        let conn = dbapi::open_db_at(store.db_path()).unwrap();
+        conn.execute(
+            "UPDATE pagetable SET project = 'work' WHERE lpath IN (?1, ?2)",
+            rusqlite::params![paths[0], paths[1]],
+        )
+        .unwrap();
+
+        let counts = store.reset_project("work").await.unwrap();
+        assert_eq!(
+            counts,
+            ResetCounts {
+                records: 2,
+                embeddings: 2
+            }
+        );
+        assert_eq!(store.count_rows().await.unwrap(), 1);
+        assert!(dbapi::get_record_in(&conn, &paths[2]).unwrap().is_some());
+        assert!(dbapi::get_record_in(&conn, &paths[0]).unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_whitespace_text_query_matches_nothing() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let store = temp_store(dir.path()).await;
+        let path = dir.path().join("note.md");
+        let embedding = embedding_for(path.to_str().unwrap(), "Note", vec![0.5; EMBEDDING_DIM]);
+        store.add_embedding(embedding).await.unwrap();
+        assert_eq!(store.count_rows().await.unwrap(), 1);
+
+        // No FTS index exists, so an untrimmed query would reach LanceDB and fail.
+        assert!(store.search_text("   ", 10).await.unwrap().is_empty());
+        assert!(store
+            .search_text_scored("\t\n", 10)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_paths_with_quotes_round_trip() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let store = temp_store(dir.path()).await;
+        let quoted = dir.path().join("john's ideas.md");
+        std::fs::write(&quoted, "ideas").unwrap();
+        let quoted = quoted.to_str().unwrap();
+
+        let embedding = embedding_for(quoted, "John's ideas", vec![0.25; EMBEDDING_DIM]);
+        store.upsert_embedding(embedding).await.unwrap();
+        let found = store.get_embedding_by_path(quoted).await.unwrap().unwrap();
+        assert_eq!(found.metadata.title, "John's ideas");
+
+        store.delete_embedding_by_path(quoted).await.unwrap();
+        assert!(store.get_embedding_by_path(quoted).await.unwrap().is_none());
+        assert_eq!(store.count_rows().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_open_at_explicit_directory() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let embeddings_dir = dir.path().join("vectors");
+
+        let db_path = dir.path().join("pagetable.sqlite");
+        let mut store = EmbeddingsStore::open_at(&embeddings_dir, &db_path, "notes")
+            .await
+            .unwrap();
+        assert!(!store.table_exists().await.unwrap());
+        store.create_table().await.unwrap();
+        assert!(embeddings_dir.exists());
+
+        let reopened = EmbeddingsStore::open_at(&embeddings_dir, &db_path, "notes")
+            .await
+            .unwrap();
+        assert_eq!(reopened.count_rows().await.unwrap(), 0);
+        assert_eq!(reopened.model_info().model.as_deref(), Some(MODEL_NAME));
+
+        // Clones and stores built on the same connection see the same table.
+        let clone = reopened.clone();
+        assert!(clone.table_exists().await.unwrap());
+        let shared =
+            EmbeddingsStore::from_connection(reopened.connection.clone(), &db_path, "notes")
+                .await
+                .unwrap();
+        assert_eq!(shared.dimension(), EMBEDDING_DIM);
+        assert_eq!(shared.db_path(), db_path.as_path());
+    }
+
+    #[tokio::test]
+    async fn test_open_at_writes_records_to_db_path() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let db_path = dir.path().join("db").join("pagetable.sqlite");
+        let mut store = EmbeddingsStore::open_at(&dir.path().join("vectors"), &db_path, "notes")
+            .await
+            .unwrap();
+        store.create_table().await.unwrap();
+
+        let path = dir.path().join("note.md").to_str().unwrap().to_string();
+        let embedding = DocumentEmbedding {
+            vector: vec![0.5; EMBEDDING_DIM],
+            metadata: EmbeddingMetadata {
+                id: String::new(),
+                title: "Note".to_string(),
+                path: path.clone(),
+            },
+            content: "body".to_string(),
+        };
+        store.add_embedding(embedding).await.unwrap();
+
+        let conn = dbapi::open_db_at(&db_path).unwrap();
+        let record = dbapi::get_record_in(&conn, &path).unwrap().unwrap();
+        assert_eq!(record.title, "Note");
+    }
+
+    #[tokio::test]
+    async fn test_clear_deletes_embeddings_and_records() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut store = temp_store(dir.path()).await;
+        let kept = dir.path().join("kept.md").to_str().unwrap().to_string();
+        let conn = store.db().unwrap();
+        dbapi::add_record_in(&conn, &pagetable_record(kept.clone(), "Kept".to_string())).unwrap();
+        let paths: Vec<String> = ["a.md", "b.md"]
+            .iter()
+            .map(|name| dir.path().join(name).to_str().unwrap().to_string())
+            .collect();
+        for path in &paths {
+            store
+                .add_embedding(embedding_for(path, "Note", vec![0.5; EMBEDDING_DIM]))
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(store.clear().await.unwrap(), 2);
+        assert!(store.list(0, 10).await.unwrap().is_empty());
+        for path in &paths {
+            assert!(dbapi::get_record_in(&conn, path).unwrap().is_none());
+        }
+        // Records without an embedding are left alone.
+        assert!(dbapi::get_record_in(&conn, &kept).unwrap().is_some());
+    }
+
+    /// Embeds every text as the same vector.
+    struct FixedEmbedder(Vec<f32>);
+
+    #[async_trait]
+    impl Embedder for FixedEmbedder {
+        async fn embed(
+            &self,
+            texts: &[&str],
+        ) -> std::result::Result<Vec<Vec<f32>>, Box<dyn std::error::Error + Send + Sync>> {
+            Ok(texts.iter().map(|_| self.0.clone()).collect())
+        }
+
+        async fn dimension(
+            &self,
+        ) -> std::result::Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+            Ok(self.0.len())
+        }
+    }
+
+    /// A unit vector along `axis`, plus `tilt` along the next one.
+    fn unit_vector(axis: usize, tilt: f32) -> Vec<f32> {
+        let mut vector = vec![0.0; EMBEDDING_DIM];
+        vector[axis] = 1.0;
+        vector[axis + 1] = tilt;
+        vector
+    }
+
+    #[tokio::test]
+    async fn test_semantic_search_maps_hits_to_records() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let store = temp_store(dir.path()).await;
+        let path = |name: &str| dir.path().join(name).to_str().unwrap().to_string();
+        let (near, close, orphan, far) = (
+            path("near.md"),
+            path("close.md"),
+            path("orphan.md"),
+            path("far.md"),
+        );
+        for (path, vector) in [
+            (&near, unit_vector(0, 0.0)),
+            (&close, unit_vector(0, 0.5)),
+            (&orphan, unit_vector(0, 0.1)),
+            (&far, unit_vector(1, 0.0)),
+        ] {
+            store
+                .add_embedding(embedding_for(path, "Note", vector))
+                .await
+                .unwrap();
+        }
+        let conn = store.db().unwrap();
+        dbapi::delete_record_in(&conn, dbapi::RecordIdentifier::Lpath(orphan.clone())).unwrap();
+
+        let embedder = FixedEmbedder(unit_vector(0, 0.0));
+        let results = semantic_search(&store, &embedder, "query", 10, 0.5)
+            .await
+            .unwrap();
+        let lpaths: Vec<&str> = results
+            .iter()
+            .map(|(record, _)| record.lpath.as_str())
+            .collect();
+        // The orthogonal note scores 0 and falls under the threshold; the one without a
+        // pagetable record is skipped.
+        assert_eq!(
+            lpaths,
+            vec![utils::normalize_path(&near), utils::normalize_path(&close)]
+        );
+        assert!((results[0].1 - 1.0).abs() < 1e-4);
+        assert!(results[1].1 >= 0.5 && results[1].1 < results[0].1);
+    }
+
+    #[test]
+    fn test_public_types_are_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<EmbeddingsStore>();
+        assert_send_sync::<DocumentEmbedding>();
+        assert_send_sync::<SearchHit>();
+        assert_send_sync::<MetadataFilter>();
+        assert_send_sync::<ModelInfo>();
+
+        fn assert_vector_store<T: VectorStore>() {}
+        assert_vector_store::<EmbeddingsStore>();
+
+        // Reindexing and searching can run in spawned tasks and server handlers.
+        fn assert_send<T: Send>(_: &T) {}
+        let store: Option<EmbeddingsStore> = None;
+        if let Some(store) = store.as_ref() {
+            let embedder = LocalEmbedder::new();
+            assert_send(&store.reindex_file_with(&embedder, "note.md"));
+            assert_send(&semantic_search(store, &embedder, "query", 10, 0.5));
+        }
+    }
+
+    #[test]
+    fn test_escape_sql_literal() {
+        assert_eq!(
+            escape_sql_literal("/notes/john's ideas.md"),
+            "/notes/john''s ideas.md"
+        );
+        assert_eq!(escape_sql_literal("plain"), "plain");
+    }
+
+    #[test]
+    fn test_distance_to_score() {
+        assert_eq!(distance_to_score(0.0, DistanceType::Cosine), 1.0);
+        assert_eq!(distance_to_score(0.25, DistanceType::Cosine), 0.75);
+        assert_eq!(distance_to_score(0.0, DistanceType::L2), 1.0);
+        assert_eq!(distance_to_score(1.0, DistanceType::L2), 0.5);
+        // Out-of-range similarities from unnormalized vectors are clamped.
+        assert_eq!(distance_to_score(-0.2, DistanceType::Cosine), 1.0);
+        assert_eq!(distance_to_score(1.5, DistanceType::Dot), 0.0);
+    }
+
+    #[test]
+    fn test_metadata_filter_and() {
+        let filter = MetadataFilter::eq(FilterColumn::Title, "Rust")
+            .and(MetadataFilter::eq(FilterColumn::Id, "42"));
+        assert_eq!(filter.as_predicate(), "(title = 'Rust') AND (id = '42')");
+    }
+}
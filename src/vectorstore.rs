@@ -0,0 +1,32 @@
+use crate::vectordbapi::{DocumentEmbedding, SearchHit};
+use async_trait::async_trait;
+
+/// A backend that stores document embeddings and searches them by vector similarity.
+///
+/// Code written against this trait works with any backend, so switching stores doesn't
+/// mean rewriting call sites. [`EmbeddingsStore`](crate::vectordbapi::EmbeddingsStore)
+/// implements it on LanceDB.
+#[async_trait]
+pub trait VectorStore: Send + Sync {
+    /// Error type returned by the backend.
+    type Error: std::error::Error + Send + Sync + 'static;
+
+    /// Stores an embedding.
+    async fn add(&self, embedding: DocumentEmbedding) -> Result<(), Self::Error>;
+
+    /// Returns up to `limit` embeddings most similar to `query_vector`, most similar first.
+    async fn search(
+        &self,
+        query_vector: &[f32],
+        limit: usize,
+    ) -> Result<Vec<SearchHit>, Self::Error>;
+
+    /// Deletes the embedding stored for `path`, if any.
+    async fn delete_by_path(&self, path: &str) -> Result<(), Self::Error>;
+
+    /// Returns the number of stored embeddings.
+    async fn count(&self) -> Result<usize, Self::Error>;
+
+    /// Builds the backend's vector index to speed up searches.
+    async fn create_index(&self) -> Result<(), Self::Error>;
+}